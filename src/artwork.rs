@@ -0,0 +1,283 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Downloading album artwork and drawing it in the terminal.
+
+use std::{collections::BTreeMap, fmt::Write as _, io::Cursor};
+
+use base64::{Engine as _, prelude::BASE64_STANDARD};
+use clap::ValueEnum;
+use eyre::{Result, bail};
+use image::{DynamicImage, ImageFormat, RgbImage, imageops::FilterType};
+use owo_colors::OwoColorize as _;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::MetadataConfig, daemon, dirs, http::HTTP, music::Track};
+
+/// Pixel size of a terminal cell to assume when the terminal doesn't report one.
+const DEFAULT_CELL_SIZE: (u32, u32) = (10, 20);
+/// Kitty graphics protocol payloads are sent in chunks of at most this many bytes.
+const KITTY_CHUNK_SIZE: usize = 4096;
+
+/// How to draw images in the terminal.
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Graphics {
+    /// Detect what the terminal supports
+    Auto,
+    /// Kitty graphics protocol (kitty, Ghostty)
+    Kitty,
+    /// iTerm2 inline images
+    Iterm,
+    /// Sixel graphics
+    Sixel,
+    /// Colored Unicode half blocks, which work everywhere
+    Blocks,
+}
+
+impl Graphics {
+    /// Resolve [`Auto`](Self::Auto) into a concrete protocol based on the environment.
+    pub fn detect(self) -> Self {
+        if self != Self::Auto {
+            return self;
+        }
+
+        let var = |name| std::env::var(name).unwrap_or_default();
+        let term = var("TERM");
+        let term_program = var("TERM_PROGRAM");
+
+        if std::env::var_os("KITTY_WINDOW_ID").is_some()
+            || term == "xterm-kitty"
+            || term_program == "ghostty"
+        {
+            Self::Kitty
+        } else if term_program == "iTerm.app"
+            || term_program == "WezTerm"
+            || var("LC_TERMINAL") == "iTerm2"
+        {
+            Self::Iterm
+        } else if term.contains("sixel") || term.starts_with("foot") || term.starts_with("mlterm") {
+            Self::Sixel
+        } else {
+            Self::Blocks
+        }
+    }
+}
+
+/// An escape sequence that removes previously drawn images, which clearing the screen
+/// doesn't do for every protocol.
+pub fn clear(graphics: Graphics) -> &'static str {
+    match graphics {
+        Graphics::Kitty => "\x1b_Ga=d,q=2\x1b\\",
+        _ => "",
+    }
+}
+
+/// Album artwork for a track.
+#[derive(Debug)]
+pub struct Artwork {
+    pub track_id: String,
+    image: DynamicImage,
+}
+
+impl Artwork {
+    /// Load the artwork for `track` from the cache, downloading it if it isn't cached yet.
+    pub async fn fetch(track: &Track, config: &MetadataConfig) -> Result<Self> {
+        if !track.id.chars().all(|c| c.is_ascii_alphanumeric()) {
+            bail!("Unexpected track ID {:?}", track.id);
+        }
+
+        let path = dirs::cache_dir()?
+            .join("artwork")
+            .join(format!("{}.jpg", track.id));
+
+        let bytes = if let Ok(bytes) = tokio::fs::read(&path).await {
+            bytes
+        } else {
+            let metadata = daemon::fetch_metadata(track, config).await?;
+            let bytes = HTTP
+                .get(&metadata.album_artwork)
+                .send()
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await?
+                .to_vec();
+
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            tokio::fs::write(&path, &bytes).await?;
+
+            bytes
+        };
+
+        Ok(Self {
+            track_id: track.id.clone(),
+            image: image::load_from_memory(&bytes)?,
+        })
+    }
+
+    /// Draw the artwork into an area of `columns` by `rows` cells using `graphics`, which must
+    /// not be [`Graphics::Auto`].
+    ///
+    /// Blocks are returned as one string per row; the other protocols produce a single
+    /// escape sequence to be written at the top left of the area.
+    pub fn render(&self, graphics: Graphics, columns: u16, rows: u16) -> Result<Vec<String>> {
+        if graphics == Graphics::Blocks {
+            return Ok(self.blocks(columns, rows));
+        }
+
+        let (cell_width, cell_height) = crossterm::terminal::window_size()
+            .ok()
+            .filter(|size| size.width > 0 && size.height > 0)
+            .map_or(DEFAULT_CELL_SIZE, |size| {
+                (
+                    u32::from(size.width / size.columns),
+                    u32::from(size.height / size.rows),
+                )
+            });
+
+        let image = self.image.resize(
+            u32::from(columns) * cell_width,
+            u32::from(rows) * cell_height,
+            FilterType::Triangle,
+        );
+
+        Ok(vec![match graphics {
+            Graphics::Kitty => kitty(&png(&image)?, columns, rows)?,
+            Graphics::Iterm => iterm(&png(&image)?, columns, rows),
+            Graphics::Sixel => sixel(&image.to_rgb8())?,
+            Graphics::Auto | Graphics::Blocks => unreachable!(),
+        }])
+    }
+
+    /// Two pixels per cell, drawn as an upper half block over a background color.
+    fn blocks(&self, columns: u16, rows: u16) -> Vec<String> {
+        let image = self
+            .image
+            .resize_exact(
+                u32::from(columns),
+                u32::from(rows) * 2,
+                FilterType::Triangle,
+            )
+            .to_rgb8();
+
+        (0..u32::from(rows))
+            .map(|row| {
+                (0..u32::from(columns))
+                    .map(|column| {
+                        let [r1, g1, b1] = image.get_pixel(column, row * 2).0;
+                        let [r2, g2, b2] = image.get_pixel(column, row * 2 + 1).0;
+
+                        "▀"
+                            .truecolor(r1, g1, b1)
+                            .on_truecolor(r2, g2, b2)
+                            .to_string()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+fn png(image: &DynamicImage) -> Result<Vec<u8>> {
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+    Ok(png)
+}
+
+fn kitty(png: &[u8], columns: u16, rows: u16) -> Result<String> {
+    let data = BASE64_STANDARD.encode(png);
+    let chunks = data.as_bytes().chunks(KITTY_CHUNK_SIZE).collect::<Vec<_>>();
+
+    let mut output = String::new();
+
+    for (index, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(index + 1 < chunks.len());
+        let chunk = String::from_utf8_lossy(chunk);
+
+        if index == 0 {
+            // Don't move the cursor, and don't reply with status messages
+            write!(
+                output,
+                "\x1b_Ga=T,f=100,c={columns},r={rows},C=1,q=2,m={more};{chunk}\x1b\\"
+            )?;
+        } else {
+            write!(output, "\x1b_Gm={more};{chunk}\x1b\\")?;
+        }
+    }
+
+    Ok(output)
+}
+
+fn iterm(png: &[u8], columns: u16, rows: u16) -> String {
+    format!(
+        "\x1b]1337;File=inline=1;size={};width={columns};height={rows};preserveAspectRatio=1:{}\x07",
+        png.len(),
+        BASE64_STANDARD.encode(png)
+    )
+}
+
+/// Encode an image as Sixel graphics, with colors reduced to a 6×6×6 cube.
+fn sixel(image: &RgbImage) -> Result<String> {
+    let quantize = |value: u8| (u16::from(value) * 5 + 127) / 255;
+
+    let (width, height) = image.dimensions();
+    let indices = image
+        .pixels()
+        .map(|pixel| {
+            let [r, g, b] = pixel.0.map(quantize);
+            r * 36 + g * 6 + b
+        })
+        .collect::<Vec<_>>();
+
+    let mut output = format!("\x1bPq\"1;1;{width};{height}");
+
+    for index in 0..216_u16 {
+        let [r, g, b] = [index / 36, index / 6 % 6, index % 6].map(|c| c * 20);
+        write!(output, "#{index};2;{r};{g};{b}")?;
+    }
+
+    for band in (0..height).step_by(6) {
+        // The sixels of each color in this band, as a bit per row for each column
+        let mut colors = BTreeMap::<u16, Vec<u8>>::new();
+
+        for y in band..(band + 6).min(height) {
+            for x in 0..width {
+                let index = indices[(y * width + x) as usize];
+                colors
+                    .entry(index)
+                    .or_insert_with(|| vec![0; width as usize])[x as usize] |= 1 << (y - band);
+            }
+        }
+
+        for (index, sixels) in colors {
+            write!(output, "#{index}")?;
+
+            let mut sixels = sixels.into_iter().peekable();
+            while let Some(bits) = sixels.next() {
+                let mut count = 1;
+                while sixels.next_if_eq(&bits).is_some() {
+                    count += 1;
+                }
+
+                let c = char::from(63 + bits);
+                if count > 3 {
+                    write!(output, "!{count}{c}")?;
+                } else {
+                    output.extend(std::iter::repeat_n(c, count));
+                }
+            }
+
+            // Return to the start of the band for the next color
+            output.push('$');
+        }
+
+        output.push('-');
+    }
+
+    output.push_str("\x1b\\");
+    Ok(output)
+}
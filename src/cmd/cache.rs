@@ -0,0 +1,73 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use tokio::fs;
+
+use anstream::println;
+use eyre::Result;
+use owo_colors::OwoColorize as _;
+
+use crate::{config::MetadataConfig, music::cache::Cache};
+
+#[expect(clippy::cast_precision_loss)]
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+
+    while size >= 1024. && unit < UNITS.len() - 1 {
+        size /= 1024.;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+pub async fn stats(config: &MetadataConfig) -> Result<()> {
+    let path = Cache::path()?;
+    let cache = Cache::load().await?;
+
+    let entries = cache.entries().count();
+    let unmatched = cache
+        .entries()
+        .filter(|entry| entry.metadata.is_none())
+        .count();
+    let expired = cache
+        .entries()
+        .filter(|entry| entry.is_expired(config))
+        .count();
+    let size = fs::metadata(&path)
+        .await
+        .map_or(0, |metadata| metadata.len());
+
+    println!("Metadata cache at {}", path.display().bold());
+    println!();
+    println!(
+        "{} {} · {} without a match",
+        "Tracks".dimmed(),
+        entries,
+        unmatched.yellow()
+    );
+    println!("{} {}", "Expired".dimmed(), expired);
+    println!(
+        "{} {} · {} tracks at most",
+        "Size".dimmed(),
+        format_size(size),
+        config.cache_size
+    );
+
+    Ok(())
+}
+
+pub async fn clear() -> Result<()> {
+    Cache::clear().await?;
+    println!("{} metadata cache", "Cleared".green());
+
+    Ok(())
+}
@@ -0,0 +1,43 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use anstream::{eprintln, println};
+use eyre::{Result, bail};
+use owo_colors::OwoColorize as _;
+
+use crate::config::Config;
+
+pub fn show() -> Result<()> {
+    let config = Config::load()?;
+    print!("{}", toml::to_string_pretty(&config)?);
+
+    Ok(())
+}
+
+pub fn path() -> Result<()> {
+    println!("{}", Config::path()?.display());
+
+    Ok(())
+}
+
+pub fn validate() -> Result<()> {
+    let path = Config::path()?;
+
+    let Some((_, unknown)) = Config::read()? else {
+        println!("{} configuration file at {}", "No".yellow(), path.display());
+        return Ok(());
+    };
+
+    if !unknown.is_empty() {
+        for key in &unknown {
+            eprintln!("{} {key}", "Unknown key".red());
+        }
+
+        bail!("{} unknown key(s) in {}", unknown.len(), path.display());
+    }
+
+    println!("{} configuration at {}", "Valid".green(), path.display());
+
+    Ok(())
+}
@@ -0,0 +1,13 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use eyre::Result;
+
+use crate::{config::Config, daemon, music::PlayerBackend};
+
+/// Run in the background, serving the player's state to other invocations and recording
+/// listening history until interrupted.
+pub async fn daemon(backend: impl PlayerBackend + 'static, config: &Config) -> Result<()> {
+    daemon::serve(backend, config).await
+}
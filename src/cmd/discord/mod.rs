@@ -10,25 +10,85 @@ use eyre::{Result, eyre};
 use owo_colors::OwoColorize as _;
 
 use crate::{
-    music,
+    config::Config,
+    daemon,
+    events::{Detector, Event, Hooks},
+    history::Recorder,
+    music::{self, PlayerBackend, PlayerState},
     rich_presence::{
         DiscordIpc, DiscordIpcClient, RichPresenceError,
         activity::{Activity, Assets, Button, Timestamps},
     },
+    scrobble::{self, Scrobbler, Service},
 };
 
 pub mod agent;
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 struct ActivityState {
     last_song_id: Option<String>,
     last_position: Option<f64>,
     is_idle: bool,
+    /// Records listening history if enabled, while the daemon isn't doing so
+    history: Option<Recorder>,
+    /// Scrobblers for the services enabled for Discord presence
+    scrobblers: Vec<Scrobbler<Box<dyn Service>>>,
+    events: Detector,
+    hooks: Hooks,
+}
+
+impl ActivityState {
+    /// Update the listening history and scrobbles, which shouldn't interrupt the presence
+    /// if they fail.
+    async fn record(&mut self, track: Option<(&music::Track, f64)>) {
+        if let Some(history) = &mut self.history {
+            // The daemon records the history itself while it is running, and may have been
+            // started or stopped since the last poll
+            let result = if daemon::Client::connect().await.is_some() {
+                history.abandon();
+                Ok(())
+            } else {
+                match track {
+                    Some((track, position)) => history.observe(track, position).await,
+                    None => history.finish().await,
+                }
+            };
+
+            if let Err(err) = result {
+                eprintln!("{} recording history: {err}", "Error".red());
+            }
+        }
+
+        for scrobbler in &mut self.scrobblers {
+            if let Some((track, position)) = track {
+                if let Err(err) = scrobbler.observe(track, position).await {
+                    eprintln!(
+                        "{} scrobbling to {}: {err}",
+                        "Error".red(),
+                        scrobbler.service().name()
+                    );
+                }
+            } else {
+                scrobbler.stop();
+            }
+        }
+    }
 }
 
 #[expect(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
-async fn update_presence(client: &mut DiscordIpcClient, state: &mut ActivityState) -> Result<()> {
-    if !music::is_running().await? {
+async fn update_presence(
+    backend: &impl PlayerBackend,
+    config: &Config,
+    client: &mut (impl DiscordIpc + Send),
+    state: &mut ActivityState,
+) -> Result<()> {
+    if !backend.is_running().await? {
+        state.record(None).await;
+
+        if state.events.running(false) {
+            state.hooks.dispatch(Event::Quit);
+        }
+
         if !state.is_idle {
             println!("{} any songs", "Not playing".yellow());
             state.last_position = None;
@@ -40,18 +100,27 @@ async fn update_presence(client: &mut DiscordIpcClient, state: &mut ActivityStat
         return Ok(());
     }
 
-    let initial_state = music::tell("get {player position, player state}").await?;
-    let mut initial_state = initial_state.split(", ");
+    state.events.running(true);
+    let player_state = backend.player_state().await?;
 
-    let position = initial_state
-        .next()
-        .ok_or_else(|| eyre!("Could not obtain player position"))?;
-    let player_state = initial_state
-        .next()
-        .ok_or_else(|| eyre!("Could not obtain player state"))?
-        .parse::<music::PlayerState>()?;
+    if player_state != PlayerState::Playing {
+        if player_state == PlayerState::Stopped {
+            state.record(None).await;
+        }
+
+        if state.events.state(player_state) && !state.hooks.is_empty() {
+            let track = if player_state == PlayerState::Paused {
+                backend.current_track().await.ok().flatten()
+            } else {
+                None
+            };
+
+            state.hooks.dispatch(Event::StateChanged {
+                state: player_state,
+                track,
+            });
+        }
 
-    if player_state != music::PlayerState::Playing {
         if !state.is_idle {
             println!("{} any songs", "Not playing".yellow());
             state.last_position = None;
@@ -63,12 +132,31 @@ async fn update_presence(client: &mut DiscordIpcClient, state: &mut ActivityStat
         return Ok(());
     }
 
-    let position = position.replace(',', ".").parse::<f64>()?;
+    let position = backend
+        .player_position()
+        .await?
+        .ok_or_else(|| eyre!("Could not obtain player position"))?;
 
-    let track = music::get_current_track()
+    let track = backend
+        .current_track()
         .await?
         .ok_or_else(|| eyre!("Could not obtain track information"))?;
 
+    state.record(Some((&track, position))).await;
+
+    if state.events.state(player_state) {
+        state.hooks.dispatch(Event::StateChanged {
+            state: player_state,
+            track: Some(track.clone()),
+        });
+    }
+
+    if state.events.track(Some(&track)) {
+        state.hooks.dispatch(Event::TrackChanged {
+            track: track.clone(),
+        });
+    }
+
     let mut ongoing = false;
 
     if let Some(last_song_id) = &state.last_song_id
@@ -80,7 +168,7 @@ async fn update_presence(client: &mut DiscordIpcClient, state: &mut ActivityStat
     }
 
     if !ongoing {
-        let metadata = match music::fetch_metadata(&track).await {
+        let metadata = match daemon::fetch_metadata(&track, &config.metadata).await {
             Ok(v) => Some(v),
             Err(e) => {
                 eprintln!("failed to fetch metadata: {e:?}");
@@ -141,25 +229,31 @@ async fn update_presence(client: &mut DiscordIpcClient, state: &mut ActivityStat
     Ok(())
 }
 
-pub async fn discord() -> Result<()> {
-    let mut client = DiscordIpcClient::new("861702238472241162");
+pub async fn discord(backend: &impl PlayerBackend, config: &Config) -> Result<()> {
+    let mut client = DiscordIpcClient::new(&config.discord.client_id);
     if client.connect().await.is_ok() {
         println!("{} to Discord", "Connected".green());
     }
 
+    let poll_interval = Duration::from_secs(config.discord.poll_interval_secs.get());
+
     let mut state = ActivityState {
         last_position: None,
         last_song_id: None,
         is_idle: false,
+        history: config.history.enabled.then(|| Recorder::new(poll_interval)),
+        scrobblers: scrobble::scrobblers(config, poll_interval, true).await?,
+        events: Detector::default(),
+        hooks: Hooks::new(&config.hooks)?,
     };
 
     let mut last_connect_failed = false;
-    let mut intvl = time::interval(Duration::from_secs(5));
+    let mut intvl = time::interval(poll_interval);
 
     loop {
         tokio::select! {
             _ = intvl.tick() => {
-                if let Err(err) = update_presence(&mut client, &mut state).await {
+                if let Err(err) = update_presence(backend, config, &mut client, &mut state).await {
                     match err.downcast_ref::<RichPresenceError>() {
                         Some(RichPresenceError::CouldNotConnect | RichPresenceError::WriteSocketFailed) => {
                             if !last_connect_failed {
@@ -184,8 +278,121 @@ pub async fn discord() -> Result<()> {
     }
 
     println!("{} Discord presence", "Shutting down".yellow());
+    state.record(None).await;
     client.clear_activity().await?;
     client.close().await?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::music::fake::{ScriptedBackend, Snapshot, track};
+
+    /// Records the activities that would be sent to Discord.
+    #[derive(Debug, Default)]
+    struct Presence {
+        client_id: String,
+        activities: Vec<serde_json::Value>,
+    }
+
+    #[async_trait]
+    impl DiscordIpc for Presence {
+        fn get_client_id(&self) -> &String {
+            &self.client_id
+        }
+
+        async fn connect_ipc(&mut self) -> Result<(), RichPresenceError> {
+            Ok(())
+        }
+
+        async fn write_once(&mut self, data: &[u8]) -> Result<(), RichPresenceError> {
+            // Headers aren't JSON
+            if let Ok(payload) = serde_json::from_slice::<serde_json::Value>(data) {
+                self.activities.push(payload["args"]["activity"].clone());
+            }
+
+            Ok(())
+        }
+
+        async fn write(&mut self, data: &[u8]) -> Result<(), RichPresenceError> {
+            self.write_once(data).await
+        }
+
+        async fn read_once(&mut self, _buffer: &mut [u8]) -> Result<(), RichPresenceError> {
+            Ok(())
+        }
+
+        async fn read(&mut self, _buffer: &mut [u8]) -> Result<(), RichPresenceError> {
+            Ok(())
+        }
+
+        async fn close(&mut self) -> Result<(), RichPresenceError> {
+            Ok(())
+        }
+    }
+
+    fn state() -> ActivityState {
+        ActivityState {
+            last_song_id: None,
+            last_position: None,
+            is_idle: false,
+            history: None,
+            scrobblers: Vec::new(),
+            events: Detector::default(),
+            hooks: Hooks::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn clears_the_activity_when_not_playing() {
+        let backend =
+            ScriptedBackend::new(Snapshot::playing(track("1", "One")).state(PlayerState::Paused))
+                .then(Snapshot {
+                    running: false,
+                    ..Snapshot::stopped()
+                });
+        let config = Config::default();
+        let mut client = Presence::default();
+        let mut state = state();
+
+        update_presence(&backend, &config, &mut client, &mut state)
+            .await
+            .unwrap();
+        assert!(state.is_idle);
+
+        backend.advance();
+        update_presence(&backend, &config, &mut client, &mut state)
+            .await
+            .unwrap();
+
+        // On every poll, in case Discord was restarted in between
+        assert!(client.activities.iter().all(serde_json::Value::is_null));
+        assert_eq!(client.activities.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn keeps_the_activity_while_the_track_plays() {
+        let backend = ScriptedBackend::new(Snapshot::playing(track("1", "One")).position(30.));
+        let config = Config::default();
+        let mut client = Presence::default();
+
+        // The track was already shown, which is when metadata is fetched for it
+        let mut state = ActivityState {
+            last_song_id: Some("1".into()),
+            last_position: Some(25.),
+            ..state()
+        };
+
+        update_presence(&backend, &config, &mut client, &mut state)
+            .await
+            .unwrap();
+
+        assert!(client.activities.is_empty());
+        assert_eq!(state.last_position, Some(25.));
+        assert!(!state.is_idle);
+    }
+}
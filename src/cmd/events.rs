@@ -0,0 +1,173 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::time::Duration;
+use tokio::{sync::mpsc, time};
+
+use anstream::println;
+use eyre::Result;
+
+use super::now::{Extras, PlaybackStateDelta, update_state};
+use crate::{
+    config::Config,
+    events::{Detector, Event, EventKind, Payload},
+    music::{PlayerBackend, PlayerState, Playlist, Track},
+};
+
+async fn poll(
+    backend: impl PlayerBackend + 'static,
+    tx: mpsc::Sender<PlaybackStateDelta>,
+    mut rx_request_track: mpsc::Receiver<bool>,
+    poll_interval: Duration,
+) -> Result<()> {
+    let extras = Extras::default();
+    let mut intvl = time::interval(poll_interval);
+
+    loop {
+        intvl.tick().await;
+        update_state(&backend, &tx, &mut rx_request_track, &extras).await?;
+    }
+}
+
+fn emit(event: Event, filter: &[EventKind]) -> Result<()> {
+    let kind = event.kind();
+
+    if kind == EventKind::Snapshot || filter.is_empty() || filter.contains(&kind) {
+        println!("{}", serde_json::to_string(&Payload::new(event))?);
+    }
+
+    Ok(())
+}
+
+/// Print an event as a line of JSON whenever something changes, starting with a snapshot
+/// of the current state, which is printed regardless of `filter`.
+pub async fn events(
+    backend: impl PlayerBackend + 'static,
+    filter: &[EventKind],
+    config: &Config,
+) -> Result<()> {
+    let poll_interval = Duration::from_millis(config.now.poll_interval_ms.get());
+
+    let (tx, mut rx) = mpsc::channel::<PlaybackStateDelta>(20);
+    let (tx_request_track, rx_request_track) = mpsc::channel::<bool>(20);
+
+    let mut poller = tokio::spawn(poll(backend, tx, rx_request_track, poll_interval));
+
+    let mut detector = Detector::default();
+    let mut started = false;
+
+    let mut running = false;
+    let mut state = PlayerState::Unknown;
+    let mut position = None;
+    let mut track: Option<Track> = None;
+    let mut playlist: Option<Playlist> = None;
+
+    loop {
+        let delta = tokio::select! {
+            delta = rx.recv() => delta,
+            result = &mut poller => return result?,
+        };
+
+        let Some(delta) = delta else {
+            break;
+        };
+
+        match delta {
+            PlaybackStateDelta::Running(is_running) => running = is_running,
+
+            PlaybackStateDelta::State(player_state) => {
+                state = player_state;
+
+                // Nothing else is polled when nothing is playing
+                if state == PlayerState::Stopped {
+                    position = None;
+                    track = None;
+                    playlist = None;
+                }
+            }
+
+            PlaybackStateDelta::Position(player_position) => position = player_position,
+
+            PlaybackStateDelta::TrackIDRequestMoreInfo(id) => {
+                let changed = track.as_ref().is_none_or(|track| track.id != id);
+                tx_request_track.send(changed).await?;
+            }
+
+            PlaybackStateDelta::Track(current) => track = current,
+
+            PlaybackStateDelta::Playlist(current) => playlist = current,
+
+            PlaybackStateDelta::Render => {
+                let quit = detector.running(running);
+                let state_changed = detector.state(state);
+                let track_changed = detector.track(track.as_ref());
+                let playlist_changed = detector.playlist(playlist.as_ref());
+                let seeked = detector.position(position, state);
+
+                if !started {
+                    started = true;
+
+                    emit(
+                        Event::Snapshot {
+                            running,
+                            state,
+                            position,
+                            track: track.clone(),
+                            playlist: playlist.clone(),
+                        },
+                        filter,
+                    )?;
+
+                    continue;
+                }
+
+                if quit {
+                    emit(Event::Quit, filter)?;
+                }
+
+                if state_changed {
+                    emit(
+                        Event::StateChanged {
+                            state,
+                            track: track.clone(),
+                        },
+                        filter,
+                    )?;
+                }
+
+                if track_changed && let Some(track) = &track {
+                    emit(
+                        Event::TrackChanged {
+                            track: track.clone(),
+                        },
+                        filter,
+                    )?;
+                }
+
+                if playlist_changed {
+                    emit(
+                        Event::PlaylistChanged {
+                            playlist: playlist.clone(),
+                        },
+                        filter,
+                    )?;
+                }
+
+                if seeked && let Some(position) = position {
+                    emit(
+                        Event::Seeked {
+                            position,
+                            track: track.clone(),
+                        },
+                        filter,
+                    )?;
+                }
+            }
+
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
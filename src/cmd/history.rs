@@ -0,0 +1,76 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use anstream::println;
+use chrono::{Local, TimeDelta};
+use eyre::Result;
+use owo_colors::OwoColorize as _;
+
+use crate::{format, history};
+
+/// Parse how far back to look from a number and a unit, e.g. `30m`, `12h`, `7d` or `2w`.
+pub fn parse_age(s: &str) -> Result<TimeDelta, String> {
+    let s = s.trim();
+    let unit_start = s
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| format!("missing unit in {s:?}, e.g. `7d`"))?;
+    let (amount, unit) = s.split_at(unit_start);
+
+    let amount = amount
+        .parse::<i64>()
+        .map_err(|_| format!("invalid amount in {s:?}"))?;
+
+    match unit {
+        "m" => TimeDelta::try_minutes(amount),
+        "h" => TimeDelta::try_hours(amount),
+        "d" => TimeDelta::try_days(amount),
+        "w" => TimeDelta::try_weeks(amount),
+        _ => return Err(format!("unknown unit {unit:?}, expected m, h, d or w")),
+    }
+    .ok_or_else(|| format!("{s:?} is too far back"))
+}
+
+/// Print the `limit` most recent plays, newest first, that started within `since` from now.
+#[expect(clippy::cast_possible_truncation)]
+pub async fn history(since: Option<TimeDelta>, limit: usize, json: bool) -> Result<()> {
+    let mut plays = history::read(since.map(|since| Local::now() - since)).await?;
+    plays.reverse();
+    plays.truncate(limit);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&plays)?);
+        return Ok(());
+    }
+
+    if plays.is_empty() {
+        println!("{} listening history", "No".red());
+    }
+
+    for play in &plays {
+        let mut played = format!(
+            "{}/{}",
+            format::format_duration_plain(play.played as i32),
+            format::format_duration_plain(play.duration as i32)
+        )
+        .dimmed()
+        .to_string();
+
+        if play.skipped {
+            played = format!("{played} {}", "skipped".yellow());
+        }
+
+        println!(
+            "{} {} {played}",
+            play.started_at.format("%b %d %H:%M").dimmed(),
+            play.name.bold(),
+        );
+        println!(
+            "             {} · {}",
+            play.artist.blue(),
+            play.album.magenta()
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,36 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use anstream::println;
+use eyre::{Result, eyre};
+use owo_colors::OwoColorize as _;
+
+use crate::{config::LyricsConfig, lyrics, music::PlayerBackend};
+
+pub async fn lyrics(backend: &impl PlayerBackend, config: &LyricsConfig) -> Result<()> {
+    let track = backend
+        .current_track()
+        .await?
+        .ok_or_else(|| eyre!("Nothing is playing"))?;
+
+    let Some(lyrics) = lyrics::find(backend, &track, config).await? else {
+        println!("{} lyrics for {}", "No".red(), track.name.bold());
+        return Ok(());
+    };
+
+    let current = backend
+        .player_position()
+        .await?
+        .and_then(|position| lyrics.current_line(position));
+
+    for (index, line) in lyrics.lines().into_iter().enumerate() {
+        match current {
+            Some(current) if index == current => println!("{}", line.cyan().bold()),
+            Some(current) if index < current => println!("{}", line.dimmed()),
+            _ => println!("{line}"),
+        }
+    }
+
+    Ok(())
+}
@@ -2,8 +2,34 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+pub mod cache;
+pub mod config;
+pub mod daemon;
 pub mod discord;
+pub mod events;
+pub mod history;
+pub mod lyrics;
+pub mod modes;
 pub mod now;
+pub mod playlist;
+pub mod queue;
+pub mod rating;
+pub mod scrobble;
+pub mod search;
+pub mod seek;
+pub mod stats;
+pub mod volume;
 
+pub use daemon::*;
 pub use discord::*;
+pub use events::*;
+pub use history::*;
+pub use lyrics::*;
+pub use modes::*;
 pub use now::*;
+pub use queue::*;
+pub use rating::*;
+pub use search::*;
+pub use seek::*;
+pub use stats::*;
+pub use volume::*;
@@ -0,0 +1,108 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use anstream::println;
+use clap::ValueEnum;
+use eyre::Result;
+use owo_colors::OwoColorize as _;
+
+use crate::music::{PlayerBackend, RepeatMode, ShuffleMode};
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ShuffleAction {
+    /// Enable shuffle
+    On,
+    /// Disable shuffle
+    Off,
+    /// Toggle shuffle
+    Toggle,
+    /// Shuffle songs
+    Songs,
+    /// Shuffle albums
+    Albums,
+    /// Shuffle groupings
+    Groupings,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum RepeatAction {
+    /// Disable repeat
+    Off,
+    /// Repeat the current track
+    One,
+    /// Repeat the current playlist
+    All,
+    /// Cycle through off, all and one
+    Cycle,
+}
+
+async fn print_shuffle(backend: &impl PlayerBackend) -> Result<()> {
+    if backend.shuffle_enabled().await? {
+        println!(
+            "Shuffle is {} ({})",
+            "on".green(),
+            backend.shuffle_mode().await?.magenta()
+        );
+    } else {
+        println!("Shuffle is {}", "off".red());
+    }
+
+    Ok(())
+}
+
+pub async fn shuffle(backend: &impl PlayerBackend, action: Option<ShuffleAction>) -> Result<()> {
+    let Some(action) = action else {
+        return print_shuffle(backend).await;
+    };
+
+    match action {
+        ShuffleAction::On => backend.set_shuffle_enabled(true).await?,
+        ShuffleAction::Off => backend.set_shuffle_enabled(false).await?,
+        ShuffleAction::Toggle => {
+            backend.refresh().await?;
+            let enabled = backend.shuffle_enabled().await?;
+            backend.set_shuffle_enabled(!enabled).await?;
+        }
+        ShuffleAction::Songs | ShuffleAction::Albums | ShuffleAction::Groupings => {
+            backend
+                .set_shuffle_mode(match action {
+                    ShuffleAction::Albums => ShuffleMode::Albums,
+                    ShuffleAction::Groupings => ShuffleMode::Groupings,
+                    _ => ShuffleMode::Songs,
+                })
+                .await?;
+            backend.set_shuffle_enabled(true).await?;
+        }
+    }
+
+    print_shuffle(backend).await
+}
+
+pub async fn repeat(backend: &impl PlayerBackend, action: Option<RepeatAction>) -> Result<()> {
+    let mode = match action {
+        None => backend.repeat_mode().await?,
+        Some(action) => {
+            let mode = match action {
+                RepeatAction::Off => RepeatMode::Off,
+                RepeatAction::One => RepeatMode::One,
+                RepeatAction::All => RepeatMode::All,
+                RepeatAction::Cycle => {
+                    backend.refresh().await?;
+                    backend.repeat_mode().await?.next()
+                }
+            };
+
+            backend.set_repeat_mode(mode).await?;
+            mode
+        }
+    };
+
+    match mode {
+        RepeatMode::Off => println!("Repeat is {}", "off".red()),
+        RepeatMode::One => println!("Repeating {}", "the current track".green()),
+        RepeatMode::All => println!("Repeating {}", "the current playlist".green()),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,545 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Layout of the full-screen `--watch` UI, which adapts to the size of the terminal.
+
+use eyre::Result;
+use owo_colors::OwoColorize as _;
+use unicode_width::{UnicodeWidthChar as _, UnicodeWidthStr as _};
+
+use crate::{
+    format,
+    lyrics::Lyrics,
+    music::{PlayerState, Track},
+};
+
+use super::{NowOptions, PlaybackState, make_bar, modes_label, track_label, volume_label};
+
+/// Terminals narrower than this get a single line instead of the full layout, as do
+/// terminals too short to fit every line of it.
+const COMPACT_WIDTH: u16 = 40;
+/// Columns kept free on either side of the full layout.
+const MARGIN: usize = 2;
+/// Artwork is left out if there is less room than this many rows.
+const MIN_ARTWORK_ROWS: u16 = 4;
+/// Artwork doesn't grow taller than this many rows.
+const MAX_ARTWORK_ROWS: u16 = 16;
+
+/// The lyrics pane is left out if there is less room than this many rows.
+const MIN_LYRICS_ROWS: u16 = 3;
+/// The lyrics pane doesn't grow taller than this many rows.
+const MAX_LYRICS_ROWS: u16 = 9;
+
+/// The queue pane is left out if there is less room than this many rows.
+const MIN_QUEUE_ROWS: u16 = 2;
+
+/// A rectangle of cells on the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Area {
+    pub column: u16,
+    pub row: u16,
+    pub columns: u16,
+    pub rows: u16,
+}
+
+/// Everything to draw on the screen.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    /// Where to draw the artwork, if there is room for it
+    pub artwork: Option<Area>,
+    pub lines: Vec<Placed>,
+}
+
+/// What is currently drawn on the screen, to tell whether it has to be cleared.
+#[derive(Debug, Default)]
+pub struct Screen {
+    pub size: (u16, u16),
+    /// The area and track ID of the artwork on screen
+    pub artwork: Option<(Area, String)>,
+    /// Rows with text on them, which have to be cleared before drawing the next frame
+    pub text_rows: Vec<u16>,
+}
+
+/// A line of styled text placed on the screen.
+#[derive(Debug, Clone)]
+pub struct Placed {
+    pub column: u16,
+    pub row: u16,
+    pub text: String,
+}
+
+/// How many columns `text` takes up in the terminal, ignoring escape sequences.
+pub fn width(text: &str) -> usize {
+    anstream::adapter::strip_str(text).to_string().width()
+}
+
+/// Shorten `text` to fit in `max` columns, ending with an ellipsis if anything was cut off.
+pub fn truncate(text: &str, max: usize) -> String {
+    if text.width() <= max {
+        return text.to_owned();
+    }
+
+    let Some(max) = max.checked_sub(1) else {
+        return String::new();
+    };
+
+    let mut truncated = String::new();
+    let mut used = 0;
+
+    for c in text.chars() {
+        let width = c.width().unwrap_or(0);
+        if used + width > max {
+            break;
+        }
+
+        truncated.push(c);
+        used += width;
+    }
+
+    truncated.push('…');
+    truncated
+}
+
+/// Shorten two texts shown side by side to fit in `max` columns together, taking from the
+/// longer one first.
+fn truncate_pair(first: &str, second: &str, max: usize) -> (String, String) {
+    let (first_width, second_width) = (first.width(), second.width());
+
+    if first_width + second_width <= max {
+        return (first.to_owned(), second.to_owned());
+    }
+
+    // Let the shorter text keep its full width if it takes up less than half
+    let first_max = if second_width <= max / 2 {
+        max - second_width
+    } else {
+        first_width.min(max / 2)
+    };
+
+    (
+        truncate(first, first_max),
+        truncate(second, max - first_max),
+    )
+}
+
+fn state_label(state: PlayerState, no_nerd_fonts: bool) -> String {
+    if no_nerd_fonts {
+        state.to_string()
+    } else {
+        state.to_icon()
+    }
+}
+
+/// Center `lines` horizontally on a screen `columns` wide, starting at row `top`.
+#[expect(clippy::cast_possible_truncation)]
+fn center(lines: Vec<String>, columns: u16, top: u16) -> Vec<Placed> {
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(index, text)| Placed {
+            column: (usize::from(columns).saturating_sub(width(&text)) / 2) as u16,
+            row: top + index as u16,
+            text,
+        })
+        .collect()
+}
+
+/// The row to start a block of `height` rows at to center it vertically.
+fn top(height: usize, rows: u16) -> u16 {
+    u16::try_from(usize::from(rows).saturating_sub(height) / 2).unwrap_or_default()
+}
+
+/// The full layout: the track, its artist and album, a progress bar spanning the screen,
+/// volume and modes, and the playlist, all centered.
+#[expect(clippy::cast_possible_truncation)]
+fn full(data: &PlaybackState, options: &NowOptions, available: usize) -> Result<Vec<String>> {
+    let (Some(position), Some(track)) = (data.position, &data.track) else {
+        return Ok(Vec::new());
+    };
+
+    let mut lines = Vec::new();
+
+    let label = track_label(track, options.no_nerd_fonts);
+    lines.push(format!(
+        "{}{label}",
+        truncate(&track.name, available.saturating_sub(width(&label))).bold()
+    ));
+
+    let (artist, album) = truncate_pair(&track.artist, &track.album, available.saturating_sub(3));
+    lines.push(format!("{} · {}", artist.blue(), album.magenta()));
+
+    let state = state_label(data.state, options.no_nerd_fonts);
+    let elapsed = format::format_duration(position as i32, false);
+    let remaining = format::format_duration(track.duration as i32, true);
+    let bar_width =
+        available.saturating_sub(width(&state) + elapsed.width() + remaining.width() + 3);
+    let bar_width = options.bar_width.map_or(bar_width, |w| {
+        bar_width.min(w.try_into().unwrap_or_default())
+    });
+    lines.push(format!(
+        "{state} {elapsed} {} {remaining}",
+        make_bar(position / track.duration, Some(i32::try_from(bar_width)?))?
+    ));
+
+    let mut status = data
+        .volume
+        .map(|volume| {
+            volume_label(volume, options.no_nerd_fonts)
+                .dimmed()
+                .to_string()
+        })
+        .unwrap_or_default();
+    status += &modes_label(data, options.no_nerd_fonts);
+    let status = status.trim_start().to_owned();
+    if !status.is_empty() {
+        lines.push(String::new());
+        lines.push(status);
+    }
+
+    if let Some(playlist) = &data.playlist {
+        let duration = format!(" ({})", format::format_duration_plain(playlist.duration));
+        let name = truncate(
+            &playlist.name,
+            available.saturating_sub("Playlist: ".len() + duration.width()),
+        );
+        lines.push(format!("Playlist: {name}{duration}").dimmed().to_string());
+    }
+
+    Ok(lines)
+}
+
+/// A window of `rows` lines of lyrics around the current line, which is highlighted.
+#[expect(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)]
+fn lyrics_pane(
+    data: &PlaybackState,
+    lyrics: &Lyrics,
+    rows: usize,
+    available: usize,
+) -> Vec<String> {
+    let lines = lyrics.lines();
+    let current = data.position.and_then(|p| lyrics.current_line(p));
+
+    // There's no telling which line of plain lyrics is being sung, so scroll through them
+    // along with the track instead
+    let center = match (lyrics, data.position, &data.track) {
+        (Lyrics::Plain(_), Some(position), Some(track)) if track.duration > 0. => {
+            (position / track.duration * lines.len() as f64) as usize
+        }
+        _ => current.unwrap_or_default(),
+    };
+
+    let start = center
+        .saturating_sub(rows / 2)
+        .min(lines.len().saturating_sub(rows));
+
+    lines
+        .iter()
+        .enumerate()
+        .skip(start)
+        .take(rows)
+        .map(|(index, line)| {
+            if Some(index) == current {
+                let line = if line.is_empty() { "♪" } else { line };
+                truncate(line, available).cyan().bold().to_string()
+            } else {
+                truncate(line, available).dimmed().to_string()
+            }
+        })
+        .collect()
+}
+
+/// Up to `rows` lines with the tracks up next under a header, with the selected one marked.
+fn queue_pane(
+    queue: &[Track],
+    selected: usize,
+    shuffle: bool,
+    rows: usize,
+    available: usize,
+) -> Vec<String> {
+    let mut lines = vec!["Up next".dimmed().to_string()];
+
+    // Music doesn't expose the shuffled order, so playlist order would be misleading
+    if shuffle {
+        lines.push(
+            truncate("Unknown while shuffling", available)
+                .dimmed()
+                .to_string(),
+        );
+        return lines;
+    }
+
+    // Keep the selection in view when the queue is longer than the pane
+    let entries = rows.saturating_sub(1);
+    let start = (selected + 1).saturating_sub(entries);
+
+    for (index, track) in queue.iter().enumerate().skip(start).take(entries) {
+        let (name, artist) = truncate_pair(&track.name, &track.artist, available.saturating_sub(5));
+
+        if index == selected {
+            lines.push(format!("› {name} · {artist}").cyan().bold().to_string());
+        } else {
+            lines.push(format!("  {name} · {artist}").dimmed().to_string());
+        }
+    }
+
+    lines
+}
+
+/// A single line with the state, track, artist and position, for small terminals.
+#[expect(clippy::cast_possible_truncation)]
+fn compact(data: &PlaybackState, options: &NowOptions, available: usize) -> String {
+    let (Some(position), Some(track)) = (data.position, &data.track) else {
+        return String::new();
+    };
+
+    let state = state_label(data.state, options.no_nerd_fonts);
+    let time = format!(
+        "{}/{}",
+        format::format_duration_plain(position as i32),
+        format::format_duration_plain(track.duration as i32)
+    );
+
+    // Spaces around the state and time and the separator between name and artist
+    let fixed = width(&state) + time.width() + 5;
+
+    if available < fixed + 2 {
+        let name = truncate(&track.name, available.saturating_sub(width(&state) + 1));
+        return format!("{state} {}", name.bold());
+    }
+
+    let (name, artist) = truncate_pair(&track.name, &track.artist, available - fixed);
+    format!(
+        "{state} {} · {} {}",
+        name.bold(),
+        artist.blue(),
+        time.dimmed()
+    )
+}
+
+/// Lay out the UI on a screen of the given size, leaving room for artwork above the track
+/// if `artwork` is set and the screen is large enough.
+pub fn render(
+    data: &PlaybackState,
+    options: &NowOptions,
+    (columns, rows): (u16, u16),
+    artwork: bool,
+) -> Result<Frame> {
+    if data.state == PlayerState::Stopped {
+        return Ok(Frame {
+            artwork: None,
+            lines: center(
+                vec![format!("Playback is {}", data.state.red())],
+                columns,
+                top(1, rows),
+            ),
+        });
+    }
+
+    let available = usize::from(columns).saturating_sub(MARGIN * 2);
+    let mut lines = full(data, options, available)?;
+
+    if columns < COMPACT_WIDTH || lines.len() > usize::from(rows) {
+        return Ok(Frame {
+            artwork: None,
+            lines: vec![Placed {
+                column: 0,
+                row: 0,
+                text: compact(data, options, usize::from(columns)),
+            }],
+        });
+    }
+
+    if options.lyrics
+        && let Some(lyrics) = &data.lyrics
+    {
+        // Keep some room for the artwork, which is also laid out around the text
+        let lyrics_rows = rows
+            .saturating_sub(u16::try_from(lines.len())? + 3)
+            .min(MAX_LYRICS_ROWS);
+
+        if lyrics_rows >= MIN_LYRICS_ROWS {
+            lines.push(String::new());
+            lines.extend(lyrics_pane(
+                data,
+                lyrics,
+                usize::from(lyrics_rows),
+                available,
+            ));
+        }
+    }
+
+    if options.queue
+        && let Some(queue) = data.queue.as_deref().filter(|queue| !queue.is_empty())
+    {
+        let queue_rows = rows
+            .saturating_sub(u16::try_from(lines.len())? + 3)
+            .min(u16::try_from(queue.len())? + 1);
+
+        if queue_rows >= MIN_QUEUE_ROWS {
+            lines.push(String::new());
+            lines.extend(queue_pane(
+                queue,
+                data.queue_cursor.selected(),
+                data.shuffle.is_some(),
+                usize::from(queue_rows),
+                available,
+            ));
+        }
+    }
+
+    let text_rows = u16::try_from(lines.len())?;
+
+    // Cells are about twice as tall as they are wide, so square artwork takes twice as
+    // many columns as rows. A blank row separates it from the text.
+    let artwork_rows = (rows.saturating_sub(text_rows + 3))
+        .min(u16::try_from(available / 2)?)
+        .min(MAX_ARTWORK_ROWS);
+
+    if artwork && artwork_rows >= MIN_ARTWORK_ROWS {
+        let top = top(usize::from(artwork_rows + 1 + text_rows), rows);
+
+        Ok(Frame {
+            artwork: Some(Area {
+                column: (columns - artwork_rows * 2) / 2,
+                row: top,
+                columns: artwork_rows * 2,
+                rows: artwork_rows,
+            }),
+            lines: center(lines, columns, top + artwork_rows + 1),
+        })
+    } else {
+        Ok(Frame {
+            artwork: None,
+            lines: center(lines, columns, top(usize::from(text_rows), rows)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use clap::Parser as _;
+
+    use super::*;
+    use crate::music::{Playlist, ShuffleMode, fake::track};
+
+    fn options(args: &[&str]) -> NowOptions {
+        NowOptions::parse_from(["now", "--watch", "--no-nerd-fonts"].iter().chain(args))
+    }
+
+    fn playing() -> PlaybackState {
+        let mut data = PlaybackState::new(Arc::default());
+        data.state = PlayerState::Playing;
+        data.position = Some(60.);
+        data.track = Some(track("1", "Song"));
+        data.volume = Some(50);
+        data.playlist = Some(Playlist {
+            name: "Mix".into(),
+            duration: 360,
+            track_count: 2,
+        });
+        data
+    }
+
+    fn text(frame: &Frame) -> Vec<String> {
+        frame
+            .lines
+            .iter()
+            .map(|line| anstream::adapter::strip_str(&line.text).to_string())
+            .collect()
+    }
+
+    #[test]
+    fn truncates_to_the_width() {
+        assert_eq!(truncate("Song", 4), "Song");
+        assert_eq!(truncate("Song", 3), "So…");
+        assert_eq!(truncate("Song", 0), "");
+        assert_eq!(truncate("日本語", 4), "日…");
+    }
+
+    #[test]
+    fn truncates_the_longer_of_a_pair() {
+        assert_eq!(
+            truncate_pair("Song", "Artist", 10),
+            ("Song".to_owned(), "Artist".to_owned())
+        );
+        assert_eq!(
+            truncate_pair("A much longer name", "Artist", 15),
+            ("A much l…".to_owned(), "Artist".to_owned())
+        );
+        assert_eq!(
+            truncate_pair("A much longer name", "A much longer artist", 15),
+            ("A much…".to_owned(), "A much …".to_owned())
+        );
+    }
+
+    #[test]
+    fn falls_back_to_one_line_when_the_layout_does_not_fit() {
+        let data = playing();
+        let options = options(&[]);
+
+        // Name, artist, bar, blank, status and playlist
+        let frame = render(&data, &options, (80, 6), false).unwrap();
+        assert_eq!(frame.lines.len(), 6);
+        assert_eq!(text(&frame)[5], "Playlist: Mix (6m0s)");
+
+        let frame = render(&data, &options, (80, 5), false).unwrap();
+        assert_eq!(frame.lines.len(), 1);
+        assert_eq!(text(&frame)[0], "playing Song · Artist 60s/3m0s");
+
+        let frame = render(&data, &options, (30, 24), false).unwrap();
+        assert_eq!(frame.lines.len(), 1);
+    }
+
+    #[test]
+    fn places_the_queue_below_the_track() {
+        let mut data = playing();
+        data.queue = Some(vec![track("2", "Next"), track("3", "Last")]);
+
+        let frame = render(&data, &options(&["--queue"]), (80, 24), false).unwrap();
+        assert_eq!(
+            &text(&frame)[6..],
+            ["", "Up next", "› Next · Artist", "  Last · Artist"]
+        );
+
+        // Centered vertically, one line per row
+        assert_eq!(frame.lines[0].row, (24 - 10) / 2);
+        assert!(
+            frame
+                .lines
+                .windows(2)
+                .all(|pair| pair[1].row == pair[0].row + 1)
+        );
+
+        data.shuffle = Some(ShuffleMode::Songs);
+        let frame = render(&data, &options(&["--queue"]), (80, 24), false).unwrap();
+        assert_eq!(
+            &text(&frame)[6..],
+            ["", "Up next", "Unknown while shuffling"]
+        );
+
+        // Left out when there isn't room for the header and a track
+        let frame = render(&data, &options(&["--queue"]), (80, 9), false).unwrap();
+        assert_eq!(frame.lines.len(), 6);
+    }
+
+    #[test]
+    fn places_artwork_above_the_text() {
+        let frame = render(&playing(), &options(&[]), (80, 24), true).unwrap();
+        let artwork = frame.artwork.unwrap();
+
+        assert_eq!(artwork.rows, 15);
+        assert_eq!(artwork.columns, artwork.rows * 2);
+        assert_eq!(artwork.column, (80 - artwork.columns) / 2);
+        assert_eq!(frame.lines[0].row, artwork.row + artwork.rows + 1);
+
+        let frame = render(&playing(), &options(&[]), (80, 12), true).unwrap();
+        assert!(frame.artwork.is_none());
+    }
+}
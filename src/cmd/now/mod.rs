@@ -0,0 +1,1226 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::{
+    artwork::{Artwork, Graphics},
+    config::{Config, LyricsConfig, MetadataConfig},
+    events::{self, Detector, Hooks},
+    format,
+    lyrics::{self, Lyrics},
+    music::{PlayerBackend, PlayerState, Playlist, RepeatMode, ShuffleMode, Track},
+    template::{self, Template},
+};
+
+use anstream::println;
+use clap::{Parser, ValueEnum};
+use crossterm::{
+    cursor,
+    event::{Event, EventStream, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue, terminal,
+};
+use eyre::{Result, bail, eyre};
+use futures::StreamExt as _;
+use owo_colors::OwoColorize as _;
+
+use std::{
+    fmt::Write as _,
+    io::{Write as _, stdout},
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::{Notify, mpsc, watch},
+    task,
+};
+
+use super::SeekTarget;
+
+mod layout;
+
+#[derive(Parser, Debug)]
+#[expect(clippy::struct_excessive_bools)]
+pub struct NowOptions {
+    /// Show a keyboard-interactive, full-screen terminal UI
+    ///
+    /// Keys: space to play or pause, `n`/`p` for the next/previous track, ←/→ to seek,
+    /// `+`/`-` to change the volume, `l` to love or unlove, `s` to toggle shuffle,
+    /// `r` to cycle the repeat mode, ↑/↓ and Enter to pick a track from the queue pane
+    /// and `q`/Esc to quit.
+    #[arg(short, long)]
+    pub watch: bool,
+
+    /// Disable Nerd Font symbols
+    #[arg(long)]
+    pub no_nerd_fonts: bool,
+
+    /// Playback progress bar width
+    #[arg(long)]
+    pub bar_width: Option<i32>,
+
+    /// Show album artwork, optionally choosing how it is drawn
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "auto")]
+    pub artwork: Option<Graphics>,
+
+    /// Show a lyrics pane, highlighting the current line of timed lyrics (requires `--watch`)
+    #[arg(long, requires = "watch")]
+    pub lyrics: bool,
+
+    /// Show the tracks up next, which can be selected with ↑/↓ and played with Enter
+    /// (requires `--watch`)
+    #[arg(long, requires = "watch")]
+    pub queue: bool,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t)]
+    pub format: OutputFormat,
+
+    /// Print a single line rendered from a template, e.g. '{artist} – {name} [{position}/{duration}]'
+    ///
+    /// Fields: `id`, `name`, `album`, `artist`, `duration`, `position`, `remaining`,
+    /// `progress`, `state`, `state_icon`, `playlist`, `playlist_duration`, `volume`.
+    ///
+    /// Filters are appended with `|`: `upper`, `lower`, `truncate:N`, `default:TEXT`,
+    /// `bold`, `dimmed`, `red`, `green`, `yellow`, `blue`, `magenta`, `cyan`.
+    #[arg(long, conflicts_with = "format")]
+    pub template: Option<Template>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum OutputFormat {
+    /// Colored, human-readable text
+    #[default]
+    Text,
+    /// A single JSON object
+    Json,
+    /// One JSON object per line on every update (requires `--watch`)
+    Ndjson,
+}
+
+/// Playback state, also serialized as-is by the JSON output formats.
+#[derive(Debug, Clone, serde::Serialize)]
+struct PlaybackState {
+    state: PlayerState,
+    /// Position in the current track in seconds
+    position: Option<f64>,
+    track: Option<Track>,
+    playlist: Option<Playlist>,
+    /// Volume from 0 to 100
+    volume: Option<u8>,
+    /// What is being shuffled, or `null` if shuffle is off
+    shuffle: Option<ShuffleMode>,
+    repeat: Option<RepeatMode>,
+    #[serde(skip)]
+    artwork: Option<Arc<Artwork>>,
+    #[serde(skip)]
+    lyrics: Option<Arc<Lyrics>>,
+    #[serde(skip)]
+    queue: Option<Vec<Track>>,
+    #[serde(skip)]
+    queue_cursor: Arc<QueueCursor>,
+    #[serde(skip)]
+    events: Detector,
+    /// How many times the player has been polled
+    #[serde(skip)]
+    polls: u64,
+}
+
+impl PlaybackState {
+    /// Nothing known yet, before the first poll.
+    fn new(queue_cursor: Arc<QueueCursor>) -> Self {
+        Self {
+            state: PlayerState::Unknown,
+            playlist: None,
+            position: None,
+            track: None,
+            volume: None,
+            shuffle: None,
+            repeat: None,
+            artwork: None,
+            lyrics: None,
+            queue: None,
+            queue_cursor,
+            events: Detector::default(),
+            polls: 0,
+        }
+    }
+
+    fn template_context(&self) -> template::Context<'_> {
+        template::Context {
+            state: self.state,
+            position: self.position,
+            track: self.track.as_ref(),
+            playlist: self.playlist.as_ref(),
+            volume: self.volume,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(super) enum PlaybackStateDelta {
+    Running(bool),
+    State(PlayerState),
+    Position(Option<f64>),
+    /// Advance the position by the given number of seconds if playing
+    PositionTick(f64),
+    TrackIDRequestMoreInfo(String),
+    Track(Option<Track>),
+    Playlist(Option<Playlist>),
+    Volume(Option<u8>),
+    Modes {
+        shuffle: Option<ShuffleMode>,
+        repeat: Option<RepeatMode>,
+    },
+    Artwork(Option<Arc<Artwork>>),
+    Lyrics(Option<Arc<Lyrics>>),
+    Queue(Option<Vec<Track>>),
+    /// Everything from a poll of the player has been sent
+    Polled,
+    Render,
+}
+
+/// What to fetch along with a new track, if it is shown.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Extras {
+    artwork: Option<MetadataConfig>,
+    lyrics: Option<LyricsConfig>,
+    queue: bool,
+}
+
+/// Poll the player and send what changed.
+pub(super) async fn update_state(
+    backend: &impl PlayerBackend,
+    tx: &mpsc::Sender<PlaybackStateDelta>,
+    rx_request_track: &mut mpsc::Receiver<bool>,
+    extras: &Extras,
+) -> Result<()> {
+    let running = backend.is_running().await?;
+    tx.send(PlaybackStateDelta::Running(running)).await?;
+
+    // Don't launch Music just to find out that nothing is playing
+    if !running {
+        tx.send(PlaybackStateDelta::State(PlayerState::Stopped))
+            .await?;
+        tx.send(PlaybackStateDelta::Polled).await?;
+        tx.send(PlaybackStateDelta::Render).await?;
+        return Ok(());
+    }
+
+    let player_state = backend.player_state().await?;
+
+    tx.send(PlaybackStateDelta::State(player_state)).await?;
+    tx.send(PlaybackStateDelta::Volume(backend.volume().await.ok()))
+        .await?;
+
+    let shuffle = if backend.shuffle_enabled().await.unwrap_or(false) {
+        backend.shuffle_mode().await.ok()
+    } else {
+        None
+    };
+    tx.send(PlaybackStateDelta::Modes {
+        shuffle,
+        repeat: backend.repeat_mode().await.ok(),
+    })
+    .await?;
+
+    if player_state != PlayerState::Stopped {
+        let track_id = backend
+            .current_track_id()
+            .await?
+            .ok_or_else(|| eyre!("Could not obtain track ID"))?;
+
+        let time_start = Instant::now();
+        let player_position = backend.player_position().await?;
+        let time_latency = time_start.elapsed().as_secs_f64();
+
+        let playlist = backend.current_playlist().await?;
+
+        tx.send(PlaybackStateDelta::Position(
+            player_position.map(|p| p + time_latency),
+        ))
+        .await?;
+
+        tx.send(PlaybackStateDelta::TrackIDRequestMoreInfo(track_id))
+            .await?;
+        let retrieve_track_data = rx_request_track.recv().await.unwrap();
+
+        if retrieve_track_data {
+            let track = backend.current_track().await?;
+
+            // Missing artwork or lyrics shouldn't get in the way of showing the track
+            if let Some(metadata) = &extras.artwork {
+                let artwork = match &track {
+                    Some(track) => Artwork::fetch(track, metadata).await.ok().map(Arc::new),
+                    None => None,
+                };
+
+                tx.send(PlaybackStateDelta::Artwork(artwork)).await?;
+            }
+
+            if let Some(config) = &extras.lyrics {
+                let lyrics = match &track {
+                    Some(track) => lyrics::find(backend, track, config)
+                        .await
+                        .ok()
+                        .flatten()
+                        .map(Arc::new),
+                    None => None,
+                };
+
+                tx.send(PlaybackStateDelta::Lyrics(lyrics)).await?;
+            }
+
+            if extras.queue {
+                let queue = backend.upcoming_tracks(QUEUE_LENGTH).await.ok();
+                tx.send(PlaybackStateDelta::Queue(queue)).await?;
+            }
+
+            tx.send(PlaybackStateDelta::Track(track)).await?;
+        }
+
+        tx.send(PlaybackStateDelta::Playlist(playlist)).await?;
+    }
+
+    tx.send(PlaybackStateDelta::Polled).await?;
+    tx.send(PlaybackStateDelta::Render).await?;
+
+    Ok(())
+}
+
+pub const BAR_CHAR: &str = "━";
+#[expect(clippy::cast_possible_truncation, clippy::cast_lossless)]
+fn make_bar(n: f64, width: Option<i32>) -> Result<String> {
+    let width = width.unwrap_or(30);
+
+    // The position can run past the duration between polls
+    let part_one = (n.clamp(0., 1.) * (width as f64)).floor() as i32;
+    let part_two = width - part_one;
+
+    let mut ret = String::new();
+    ret += &BAR_CHAR.repeat(part_one.try_into()?);
+    ret += &BAR_CHAR.dimmed().to_string().repeat(part_two.try_into()?);
+
+    Ok(ret)
+}
+
+fn volume_label(volume: u8, no_nerd_fonts: bool) -> String {
+    if no_nerd_fonts {
+        format!("vol {volume}%")
+    } else {
+        let icon = match volume {
+            0 => "",
+            1..50 => "",
+            _ => "",
+        };
+
+        format!("{icon} {volume}%")
+    }
+}
+
+/// Labels for whether the track is favorited or disliked and its rating, preceded by a space
+/// if there are any.
+fn track_label(track: &Track, no_nerd_fonts: bool) -> String {
+    let mut labels = Vec::new();
+
+    if track.favorited {
+        labels.push(if no_nerd_fonts { "♥" } else { "" }.red().to_string());
+    } else if track.disliked {
+        labels.push(
+            if no_nerd_fonts { "disliked" } else { "" }
+                .dimmed()
+                .to_string(),
+        );
+    }
+
+    if track.rating > 0 {
+        labels.push(super::stars(track.rating).yellow().to_string());
+    }
+
+    if labels.is_empty() {
+        String::new()
+    } else {
+        format!(" {}", labels.join(" "))
+    }
+}
+
+/// Labels for the active shuffle and repeat modes, preceded by a space if there are any.
+fn modes_label(data: &PlaybackState, no_nerd_fonts: bool) -> String {
+    let mut labels = Vec::new();
+
+    if let Some(shuffle) = data.shuffle {
+        labels.push(if no_nerd_fonts {
+            format!("shuffle {shuffle}")
+        } else {
+            "󰒟".to_owned()
+        });
+    }
+
+    if let Some(repeat) = data.repeat.filter(|r| *r != RepeatMode::Off) {
+        labels.push(if no_nerd_fonts {
+            format!("repeat {repeat}")
+        } else {
+            repeat.to_icon()
+        });
+    }
+
+    if labels.is_empty() {
+        String::new()
+    } else {
+        format!(" {}", labels.join(" ").cyan())
+    }
+}
+
+#[expect(clippy::unused_async)]
+async fn update_display(
+    data: &PlaybackState,
+    options: &NowOptions,
+    screen: &mut layout::Screen,
+) -> Result<()> {
+    if let Some(template) = &options.template {
+        println!("{}", template.render(&data.template_context()));
+        return Ok(());
+    }
+
+    match options.format {
+        OutputFormat::Text => display_text(data, options, screen)?,
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(data)?),
+        OutputFormat::Ndjson => println!("{}", serde_json::to_string(data)?),
+    }
+
+    Ok(())
+}
+
+#[expect(clippy::cast_possible_truncation)]
+fn display_text(
+    data: &PlaybackState,
+    options: &NowOptions,
+    screen: &mut layout::Screen,
+) -> Result<()> {
+    if options.watch {
+        return draw_screen(data, options, screen);
+    }
+
+    if let Some(graphics) = options.artwork
+        && let Some(artwork) = &data.artwork
+        && data.state != PlayerState::Stopped
+    {
+        draw_artwork(artwork, graphics)?;
+    }
+
+    let mut output = String::new();
+
+    if data.state == PlayerState::Stopped {
+        writeln!(output, "Playback is {}", data.state.red())?;
+    } else if let Some(position) = &data.position
+        && let Some(track) = &data.track
+    {
+        writeln!(
+            output,
+            "{}{}",
+            track.name.bold(),
+            track_label(track, options.no_nerd_fonts)
+        )?;
+        writeln!(
+            output,
+            "{} {} {} {}{}{}",
+            if options.no_nerd_fonts {
+                data.state.to_string()
+            } else {
+                data.state.to_icon()
+            },
+            format::format_duration(*position as i32, false),
+            make_bar(position / track.duration, options.bar_width)?,
+            format::format_duration(track.duration as i32, true),
+            data.volume
+                .map(|volume| format!(" {}", volume_label(volume, options.no_nerd_fonts).dimmed()))
+                .unwrap_or_default(),
+            modes_label(data, options.no_nerd_fonts),
+        )?;
+        writeln!(
+            output,
+            "{} · {}",
+            track.artist.blue(),
+            track.album.magenta()
+        )?;
+
+        if let Some(playlist) = &data.playlist {
+            writeln!(
+                output,
+                "{}",
+                format!(
+                    "Playlist: {} ({})",
+                    playlist.name,
+                    format::format_duration_plain(playlist.duration)
+                )
+                .dimmed()
+            )?;
+        }
+    }
+
+    let mut stdout = anstream::stdout().lock();
+    write!(stdout, "{output}")?;
+    stdout.flush()?;
+
+    Ok(())
+}
+
+/// Seconds to seek by with the arrow keys.
+const SEEK_STEP: f64 = 10.;
+/// How much to change the volume by with `+` and `-`.
+const VOLUME_STEP: i16 = 5;
+/// How many upcoming tracks to show in the queue pane.
+const QUEUE_LENGTH: usize = 5;
+
+/// Puts the terminal into raw mode on an alternate screen for the interactive UI,
+/// restoring it when dropped or if the program panics.
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter() -> Result<Self> {
+        let hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            restore_terminal();
+            hook(info);
+        }));
+
+        terminal::enable_raw_mode()?;
+        execute!(stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+
+        Ok(Self)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+fn restore_terminal() {
+    let _ = execute!(stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+    let _ = terminal::disable_raw_mode();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum KeyAction {
+    Toggle,
+    Next,
+    Previous,
+    Seek(f64),
+    Volume(i16),
+    Love,
+    Shuffle,
+    Repeat,
+    /// Move the selection in the queue pane
+    Select(isize),
+    /// Play the track selected in the queue pane
+    PlaySelected,
+    Quit,
+}
+
+impl KeyAction {
+    fn from_event(event: &KeyEvent) -> Option<Self> {
+        if event.kind != KeyEventKind::Press {
+            return None;
+        }
+
+        match event.code {
+            KeyCode::Char(' ') => Some(Self::Toggle),
+            KeyCode::Char('n') => Some(Self::Next),
+            KeyCode::Char('p') => Some(Self::Previous),
+            KeyCode::Left => Some(Self::Seek(-SEEK_STEP)),
+            KeyCode::Right => Some(Self::Seek(SEEK_STEP)),
+            KeyCode::Char('+' | '=') => Some(Self::Volume(VOLUME_STEP)),
+            KeyCode::Char('-') => Some(Self::Volume(-VOLUME_STEP)),
+            KeyCode::Char('l') => Some(Self::Love),
+            KeyCode::Char('s') => Some(Self::Shuffle),
+            KeyCode::Char('r') => Some(Self::Repeat),
+            KeyCode::Up => Some(Self::Select(-1)),
+            KeyCode::Down => Some(Self::Select(1)),
+            KeyCode::Enter => Some(Self::PlaySelected),
+            // Raw mode swallows the interrupt signal
+            KeyCode::Char('c') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Self::Quit)
+            }
+            KeyCode::Char('q') | KeyCode::Esc => Some(Self::Quit),
+            _ => None,
+        }
+    }
+
+    /// Carry out the action, returning whether the player should be polled again to show
+    /// its effects. `queue` is only given when the queue pane is shown.
+    async fn perform(
+        self,
+        backend: &impl PlayerBackend,
+        tx: &mpsc::Sender<PlaybackStateDelta>,
+        queue: Option<&QueueCursor>,
+    ) -> Result<bool> {
+        match self {
+            Self::Toggle => {
+                backend.refresh().await?;
+
+                if backend.player_state().await? == PlayerState::Playing {
+                    backend.pause().await?;
+                } else {
+                    backend.play().await?;
+                }
+            }
+            Self::Next => backend.next_track().await?,
+            Self::Previous => backend.previous_track().await?,
+            Self::Seek(offset) => {
+                super::seek_to(backend, SeekTarget::Relative(offset)).await?;
+            }
+            Self::Volume(steps) => {
+                backend.refresh().await?;
+                let volume = (i16::from(backend.volume().await?) + steps).clamp(0, 100);
+                backend.set_volume(u8::try_from(volume)?).await?;
+            }
+            Self::Love => {
+                backend.refresh().await?;
+
+                let track = backend
+                    .current_track()
+                    .await?
+                    .ok_or_else(|| eyre!("Nothing is playing"))?;
+
+                if !track.favorited && track.disliked {
+                    backend.set_disliked(false).await?;
+                }
+                backend.set_favorited(!track.favorited).await?;
+
+                // Track details are otherwise only fetched when the track changes
+                tx.send(PlaybackStateDelta::Track(backend.current_track().await?))
+                    .await?;
+            }
+            Self::Shuffle => {
+                backend.refresh().await?;
+                let enabled = backend.shuffle_enabled().await?;
+                backend.set_shuffle_enabled(!enabled).await?;
+            }
+            Self::Repeat => {
+                backend.refresh().await?;
+                let mode = backend.repeat_mode().await?;
+                backend.set_repeat_mode(mode.next()).await?;
+            }
+            Self::Select(offset) => {
+                if let Some(queue) = queue {
+                    queue.select(offset);
+                    tx.send(PlaybackStateDelta::Render).await?;
+                }
+
+                return Ok(false);
+            }
+            Self::PlaySelected => {
+                let Some(queue) = queue else {
+                    return Ok(false);
+                };
+
+                // The pane doesn't list the shuffled order, so there is nothing to pick from
+                if backend.shuffle_enabled().await? {
+                    return Ok(false);
+                }
+
+                backend.play_upcoming(queue.selected() + 1).await?;
+                queue.select(isize::MIN);
+            }
+            Self::Quit => {}
+        }
+
+        Ok(true)
+    }
+}
+
+/// Which entry of the queue pane is selected, shared between the key and render tasks.
+#[derive(Debug, Default)]
+struct QueueCursor {
+    selected: AtomicUsize,
+    len: AtomicUsize,
+}
+
+impl QueueCursor {
+    fn selected(&self) -> usize {
+        self.selected.load(Ordering::Relaxed)
+    }
+
+    /// Move the selection by `offset` entries, staying within the queue.
+    fn select(&self, offset: isize) {
+        let last = self.len.load(Ordering::Relaxed).saturating_sub(1);
+        let selected = self.selected().saturating_add_signed(offset).min(last);
+        self.selected.store(selected, Ordering::Relaxed);
+    }
+
+    /// Update the length of the queue, keeping the selection within it.
+    fn set_len(&self, len: usize) {
+        self.len.store(len, Ordering::Relaxed);
+        self.select(0);
+    }
+}
+
+/// Size of the artwork printed above the track by a one-off `am now`, in cells.
+const ARTWORK_SIZE: (u16, u16) = (20, 10);
+
+/// Print artwork above the text of a one-off `am now`.
+fn draw_artwork(artwork: &Artwork, graphics: Graphics) -> Result<()> {
+    let (columns, rows) = ARTWORK_SIZE;
+    let rendered = artwork.render(graphics, columns, rows)?;
+
+    let mut stdout = anstream::stdout().lock();
+
+    if graphics == Graphics::Blocks {
+        for line in rendered {
+            writeln!(stdout, "{line}")?;
+        }
+    } else {
+        // Make room for the image first, since protocols differ in where they leave the cursor
+        write!(stdout, "{}", "\n".repeat(usize::from(rows)))?;
+        queue!(stdout, cursor::MoveUp(rows), cursor::SavePosition)?;
+        write!(stdout, "{}", rendered.concat())?;
+        queue!(stdout, cursor::RestorePosition, cursor::MoveDown(rows))?;
+        writeln!(stdout)?;
+    }
+
+    stdout.flush()?;
+
+    Ok(())
+}
+
+/// Draw the full-screen UI, laid out for the current terminal size.
+///
+/// The screen is only cleared completely when the artwork has to be redrawn; otherwise just the
+/// lines of text are replaced, since images can be expensive to send.
+fn draw_screen(
+    data: &PlaybackState,
+    options: &NowOptions,
+    screen: &mut layout::Screen,
+) -> Result<()> {
+    // Cursor movements are written directly, so colors have to be stripped by hand
+    let strip = anstream::AutoStream::choice(&stdout()) == anstream::ColorChoice::Never;
+
+    let graphics = options.artwork.filter(|_| !strip);
+    let size = terminal::size()?;
+    let frame = layout::render(data, options, size, graphics.is_some())?;
+
+    let mut stdout = stdout().lock();
+
+    let artwork = frame
+        .artwork
+        .zip(data.artwork.as_ref())
+        .map(|(area, artwork)| (area, artwork.track_id.clone()));
+
+    if size == screen.size && artwork == screen.artwork {
+        for row in screen.text_rows.drain(..) {
+            queue!(
+                stdout,
+                cursor::MoveTo(0, row),
+                terminal::Clear(terminal::ClearType::CurrentLine)
+            )?;
+        }
+    } else {
+        if let Some(graphics) = graphics {
+            write!(stdout, "{}", crate::artwork::clear(graphics))?;
+        }
+        queue!(stdout, terminal::Clear(terminal::ClearType::All))?;
+
+        if let (Some(graphics), Some((area, _)), Some(artwork)) =
+            (graphics, &artwork, &data.artwork)
+        {
+            for (index, line) in artwork
+                .render(graphics, area.columns, area.rows)?
+                .into_iter()
+                .enumerate()
+            {
+                #[expect(clippy::cast_possible_truncation)]
+                queue!(stdout, cursor::MoveTo(area.column, area.row + index as u16))?;
+                write!(stdout, "{line}")?;
+            }
+        }
+
+        screen.size = size;
+        screen.artwork = artwork;
+        screen.text_rows.clear();
+    }
+
+    for line in frame.lines {
+        queue!(stdout, cursor::MoveTo(line.column, line.row))?;
+
+        if strip {
+            write!(stdout, "{}", anstream::adapter::strip_str(&line.text))?;
+        } else {
+            write!(stdout, "{}", line.text)?;
+        }
+
+        screen.text_rows.push(line.row);
+    }
+
+    stdout.flush()?;
+
+    Ok(())
+}
+
+async fn receive_delta(
+    data: &mut PlaybackState,
+    delta: &PlaybackStateDelta,
+    options: &NowOptions,
+    tx_request_track: &mpsc::Sender<bool>,
+    screen: &mut layout::Screen,
+    hooks: &Hooks,
+) -> Result<()> {
+    match delta {
+        PlaybackStateDelta::Running(running) => {
+            if data.events.running(*running) {
+                hooks.dispatch(events::Event::Quit);
+            }
+        }
+
+        PlaybackStateDelta::State(state) => {
+            if data.events.state(*state) {
+                hooks.dispatch(events::Event::StateChanged {
+                    state: *state,
+                    track: data.track.clone(),
+                });
+            }
+
+            data.state = *state;
+        }
+
+        PlaybackStateDelta::Track(track) => {
+            if data.events.track(track.as_ref())
+                && let Some(track) = track
+            {
+                hooks.dispatch(events::Event::TrackChanged {
+                    track: track.clone(),
+                });
+            }
+
+            data.track.clone_from(track);
+        }
+
+        PlaybackStateDelta::Playlist(playlist) => {
+            data.playlist.clone_from(playlist);
+        }
+
+        PlaybackStateDelta::Volume(volume) => {
+            data.volume = *volume;
+        }
+
+        PlaybackStateDelta::Modes { shuffle, repeat } => {
+            data.shuffle = *shuffle;
+            data.repeat = *repeat;
+        }
+
+        PlaybackStateDelta::Artwork(artwork) => {
+            data.artwork.clone_from(artwork);
+        }
+
+        PlaybackStateDelta::Lyrics(lyrics) => {
+            data.lyrics.clone_from(lyrics);
+        }
+
+        PlaybackStateDelta::Queue(queue) => {
+            data.queue_cursor
+                .set_len(queue.as_ref().map_or(0, Vec::len));
+            data.queue.clone_from(queue);
+        }
+
+        PlaybackStateDelta::Position(position) => {
+            data.position = *position;
+        }
+
+        PlaybackStateDelta::PositionTick(elapsed) => {
+            if data.state == PlayerState::Playing
+                && let Some(position) = data.position
+            {
+                data.position = Some(position + elapsed);
+            }
+        }
+
+        PlaybackStateDelta::TrackIDRequestMoreInfo(id) => {
+            if let Some(track) = &data.track {
+                tx_request_track.send(track.id != *id).await.unwrap();
+            } else {
+                tx_request_track.send(true).await.unwrap();
+            }
+        }
+
+        PlaybackStateDelta::Polled => {
+            data.polls += 1;
+        }
+
+        PlaybackStateDelta::Render => {
+            if options.watch || data.polls > 0 {
+                update_display(data, options, screen).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn now(
+    backend: impl PlayerBackend + 'static,
+    mut options: NowOptions,
+    config: &Config,
+) -> Result<()> {
+    match options.format {
+        OutputFormat::Json if options.watch => {
+            bail!("`--format json` cannot be used with `--watch`, use `--format ndjson` instead")
+        }
+        OutputFormat::Ndjson if !options.watch => {
+            bail!("`--format ndjson` can only be used with `--watch`")
+        }
+        _ => {}
+    }
+
+    let plain_text = options.format == OutputFormat::Text && options.template.is_none();
+    let watch = options.watch && plain_text;
+
+    options.no_nerd_fonts |= !config.now.nerd_fonts;
+    options.bar_width = options.bar_width.or(config.now.bar_width);
+
+    // Images and lyrics can't be shown in machine-readable or templated output
+    options.artwork = options
+        .artwork
+        .or(config.now.artwork)
+        .filter(|_| plain_text)
+        .map(Graphics::detect);
+    options.lyrics = (options.lyrics || config.now.lyrics) && watch;
+    options.queue = (options.queue || config.now.queue) && watch;
+
+    let extras = Extras {
+        artwork: options.artwork.map(|_| config.metadata.clone()),
+        lyrics: options.lyrics.then(|| config.lyrics.clone()),
+        queue: options.queue,
+    };
+    let queue = Arc::new(QueueCursor::default());
+
+    // Changes can only be seen while watching, and the terminal then belongs to the UI
+    let hooks = if watch {
+        Hooks::new(&config.hooks)?.log_to_file()?
+    } else {
+        Hooks::default()
+    };
+
+    let poll_interval = Duration::from_millis(config.now.poll_interval_ms.get());
+    let tick_interval = Duration::from_millis(config.now.tick_interval_ms.get());
+
+    let _terminal_guard = if watch {
+        Some(TerminalGuard::enter()?)
+    } else {
+        None
+    };
+
+    let backend = Arc::new(backend);
+    // Polls immediately, so that the effects of key presses show up without delay
+    let refresh = Arc::new(Notify::new());
+
+    let (tx, mut rx) = mpsc::channel::<PlaybackStateDelta>(20);
+    let (tx_request_track, mut rx_request_track) = mpsc::channel::<bool>(20);
+
+    let (shutdown_tx, shutdown_rx) = watch::channel(());
+
+    let mut tasks = task::JoinSet::<Result<()>>::new();
+
+    tasks.spawn({
+        let backend = backend.clone();
+        let refresh = refresh.clone();
+        let tx = tx.clone();
+        let mut shutdown_rx = shutdown_rx.clone();
+
+        async move {
+            let mut intvl = tokio::time::interval(poll_interval);
+
+            loop {
+                tokio::select! {
+                    _ = intvl.tick() => {
+                        update_state(&*backend, &tx, &mut rx_request_track, &extras).await?;
+                    }
+                    () = refresh.notified() => {
+                        update_state(&*backend, &tx, &mut rx_request_track, &extras).await?;
+                        intvl.reset();
+                    }
+                    _ = shutdown_rx.changed() => break,
+                }
+            }
+
+            Ok(())
+        }
+    });
+
+    if watch {
+        let show_queue = options.queue;
+
+        tasks.spawn({
+            let tx = tx.clone();
+            let queue = queue.clone();
+            let shutdown_tx = shutdown_tx.clone();
+            let mut shutdown_rx = shutdown_rx.clone();
+
+            async move {
+                let mut events = EventStream::new();
+
+                loop {
+                    tokio::select! {
+                        event = events.next() => {
+                            let Some(event) = event else { break };
+
+                            match event? {
+                                Event::Key(key) => {
+                                    let Some(action) = KeyAction::from_event(&key) else {
+                                        continue;
+                                    };
+
+                                    if action == KeyAction::Quit {
+                                        let _ = shutdown_tx.send(());
+                                        break;
+                                    }
+
+                                    // A failed command shouldn't take down the UI, and the
+                                    // next poll shows what actually happened anyway
+                                    let queue = show_queue.then_some(&*queue);
+                                    if let Ok(true) = action.perform(&*backend, &tx, queue).await {
+                                        refresh.notify_one();
+                                    }
+                                }
+                                Event::Resize(..) => tx.send(PlaybackStateDelta::Render).await?,
+                                _ => {}
+                            }
+                        }
+                        _ = shutdown_rx.changed() => break,
+                    }
+                }
+
+                Ok(())
+            }
+        });
+    }
+
+    // Interpolated positions are only useful for the text UI; machine-readable
+    // and templated output is emitted once per poll instead.
+    if plain_text {
+        tasks.spawn({
+            let mut shutdown_rx = shutdown_rx.clone();
+            let tx = tx.clone();
+
+            async move {
+                let mut intvl = tokio::time::interval(tick_interval);
+
+                loop {
+                    tokio::select! {
+                        _ = intvl.tick() => {
+                            tx.send(PlaybackStateDelta::PositionTick(tick_interval.as_secs_f64()))
+                                .await?;
+                            tx.send(PlaybackStateDelta::Render).await?;
+                        }
+                        _ = shutdown_rx.changed() => break,
+                    }
+                }
+
+                Ok(())
+            }
+        });
+    }
+
+    tasks.spawn({
+        let mut shutdown_rx = shutdown_rx.clone();
+        let shutdown_tx = shutdown_tx.clone();
+
+        async move {
+            let mut local_state = PlaybackState::new(queue.clone());
+            let mut screen = layout::Screen::default();
+
+            loop {
+                tokio::select! {
+                    delta = rx.recv() => {
+                        if let Some(delta) = delta {
+                            receive_delta(&mut local_state, &delta, &options, &tx_request_track, &mut screen, &hooks).await?;
+
+                            if let PlaybackStateDelta::Render = delta
+                                && !options.watch
+                                && local_state.polls > 0 {
+                                    let _ = shutdown_tx.send(());
+                                }
+                        }
+                    }
+                    _ = shutdown_rx.changed() => break,
+                };
+            }
+
+            Ok(())
+        }
+    });
+
+    let _ = tasks
+        .join_all()
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>>>();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music::fake::{ScriptedBackend, Snapshot, track};
+
+    /// Poll `backend` once, applying what changed to `data` and returning it.
+    async fn poll(
+        backend: &ScriptedBackend,
+        data: &mut PlaybackState,
+        extras: &Extras,
+    ) -> Vec<PlaybackStateDelta> {
+        let (tx, mut rx) = mpsc::channel(20);
+        let (tx_request_track, mut rx_request_track) = mpsc::channel(20);
+
+        let options = NowOptions::try_parse_from(["now"]).unwrap();
+        let mut screen = layout::Screen::default();
+        let hooks = Hooks::default();
+
+        let (result, deltas) = tokio::join!(
+            update_state(backend, &tx, &mut rx_request_track, extras),
+            async {
+                let mut deltas = Vec::new();
+
+                while let Some(delta) = rx.recv().await {
+                    // Stop before rendering, which would print the state
+                    if let PlaybackStateDelta::Render = delta {
+                        break;
+                    }
+
+                    receive_delta(
+                        data,
+                        &delta,
+                        &options,
+                        &tx_request_track,
+                        &mut screen,
+                        &hooks,
+                    )
+                    .await
+                    .unwrap();
+                    deltas.push(delta);
+                }
+
+                deltas
+            }
+        );
+
+        result.unwrap();
+        deltas
+    }
+
+    fn fetched_track(deltas: &[PlaybackStateDelta]) -> bool {
+        deltas
+            .iter()
+            .any(|delta| matches!(delta, PlaybackStateDelta::Track(_)))
+    }
+
+    #[tokio::test]
+    async fn fetches_the_track_when_it_changes() {
+        let playlist = Playlist {
+            name: "Mix".into(),
+            duration: 360,
+            track_count: 2,
+        };
+        let backend = ScriptedBackend::new(
+            Snapshot::playing(track("1", "One"))
+                .position(42.)
+                .playlist(playlist.clone()),
+        )
+        .then(Snapshot::playing(track("2", "Two")));
+
+        let mut data = PlaybackState::new(Arc::default());
+        let extras = Extras::default();
+
+        assert!(fetched_track(&poll(&backend, &mut data, &extras).await));
+        assert_eq!(data.track.as_ref().unwrap().id, "1");
+        assert_eq!(data.playlist, Some(playlist));
+        assert!(data.position.unwrap() >= 42.);
+
+        assert!(!fetched_track(&poll(&backend, &mut data, &extras).await));
+
+        backend.advance();
+
+        assert!(fetched_track(&poll(&backend, &mut data, &extras).await));
+        assert_eq!(data.track.as_ref().unwrap().id, "2");
+        assert_eq!(data.polls, 3);
+    }
+
+    #[tokio::test]
+    async fn completes_a_poll_when_music_is_not_running() {
+        let backend = ScriptedBackend::new(Snapshot {
+            running: false,
+            ..Snapshot::stopped()
+        });
+        let mut data = PlaybackState::new(Arc::default());
+
+        poll(&backend, &mut data, &Extras::default()).await;
+
+        assert_eq!(data.state, PlayerState::Stopped);
+        assert_eq!(data.polls, 1);
+    }
+
+    #[tokio::test]
+    async fn love_key_favorites_the_track() {
+        let backend = ScriptedBackend::new(Snapshot::playing(track("1", "One")));
+        let (tx, mut rx) = mpsc::channel(20);
+
+        KeyAction::Love.perform(&backend, &tx, None).await.unwrap();
+
+        assert_eq!(backend.commands(), ["set favorited"]);
+        assert!(matches!(
+            rx.recv().await,
+            Some(PlaybackStateDelta::Track(Some(track))) if track.favorited
+        ));
+    }
+
+    #[tokio::test]
+    async fn fetches_lyrics_with_the_track() {
+        let backend =
+            ScriptedBackend::new(Snapshot::playing(track("1", "One")).lyrics("[00:01.00] Hello"));
+
+        let mut data = PlaybackState::new(Arc::default());
+        let extras = Extras {
+            lyrics: Some(LyricsConfig::default()),
+            ..Extras::default()
+        };
+
+        poll(&backend, &mut data, &extras).await;
+
+        assert!(data.lyrics.is_some());
+    }
+
+    #[tokio::test]
+    async fn fetches_the_queue_with_the_track() {
+        let playlist = Playlist {
+            name: "Mix".into(),
+            duration: 540,
+            track_count: 3,
+        };
+        let backend =
+            ScriptedBackend::new(Snapshot::playing(track("1", "One")).playlist(playlist.clone()))
+                .playlist_tracks(
+                    playlist,
+                    vec![track("1", "One"), track("2", "Two"), track("3", "Three")],
+                );
+
+        let mut data = PlaybackState::new(Arc::default());
+        let extras = Extras {
+            queue: true,
+            ..Extras::default()
+        };
+
+        poll(&backend, &mut data, &extras).await;
+
+        let queue = data.queue.unwrap();
+        assert_eq!(
+            queue
+                .iter()
+                .map(|track| track.id.as_str())
+                .collect::<Vec<_>>(),
+            ["2", "3"]
+        );
+    }
+}
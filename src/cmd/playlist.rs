@@ -0,0 +1,264 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{fmt::Write as _, path::Path};
+
+use anstream::{eprintln, print, println};
+use clap::ValueEnum;
+use eyre::{Result, bail, eyre};
+use owo_colors::OwoColorize as _;
+
+use crate::{
+    format,
+    music::{PlayerBackend, Playlist, Track},
+};
+
+use super::print_track;
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum ExportFormat {
+    /// Extended M3U playlist of local files
+    #[default]
+    M3u,
+    /// JSON object with the playlist and its tracks
+    Json,
+    /// CSV with one track per row
+    Csv,
+}
+
+/// Find the playlist named `name`, preferring an exact match over one that only differs in case.
+async fn find(backend: &impl PlayerBackend, name: &str) -> Result<Playlist> {
+    let playlists = backend.playlists().await?;
+
+    playlists
+        .iter()
+        .find(|p| p.name == name)
+        .or_else(|| playlists.iter().find(|p| p.name.eq_ignore_ascii_case(name)))
+        .cloned()
+        .ok_or_else(|| eyre!("No playlist named {name:?}"))
+}
+
+fn summary(playlist: &Playlist) -> String {
+    format!(
+        "{} {} · {}",
+        playlist.track_count,
+        if playlist.track_count == 1 {
+            "track"
+        } else {
+            "tracks"
+        },
+        format::format_duration_plain(playlist.duration)
+    )
+}
+
+pub async fn list(backend: &impl PlayerBackend) -> Result<()> {
+    let playlists = backend.playlists().await?;
+
+    if playlists.is_empty() {
+        println!("{} playlists", "No".red());
+    }
+
+    for playlist in &playlists {
+        println!("{} {}", playlist.name.bold(), summary(playlist).dimmed());
+    }
+
+    Ok(())
+}
+
+pub async fn show(backend: &impl PlayerBackend, name: &str) -> Result<()> {
+    let playlist = find(backend, name).await?;
+    let tracks = backend.playlist_tracks(&playlist.name).await?;
+
+    println!("{} {}", playlist.name.bold(), summary(&playlist).dimmed());
+
+    for (index, track) in tracks.iter().enumerate() {
+        print_track(index, track);
+    }
+
+    Ok(())
+}
+
+pub async fn play(backend: &impl PlayerBackend, name: &str, shuffle: bool) -> Result<()> {
+    let playlist = find(backend, name).await?;
+
+    if shuffle {
+        backend.set_shuffle_enabled(true).await?;
+    }
+
+    backend.play_playlist(&playlist.name).await
+}
+
+pub async fn create(backend: &impl PlayerBackend, name: &str) -> Result<()> {
+    if backend.playlists().await?.iter().any(|p| p.name == name) {
+        bail!("A playlist named {name:?} already exists");
+    }
+
+    backend.create_playlist(name).await?;
+    println!("{} playlist {}", "Created".green(), name.bold());
+
+    Ok(())
+}
+
+async fn current_track(backend: &impl PlayerBackend) -> Result<Track> {
+    backend
+        .current_track()
+        .await?
+        .ok_or_else(|| eyre!("Nothing is playing"))
+}
+
+pub async fn add(backend: &impl PlayerBackend, name: &str) -> Result<()> {
+    let playlist = find(backend, name).await?;
+    let track = current_track(backend).await?;
+
+    backend.add_to_playlist(&playlist.name, &track.id).await?;
+    println!(
+        "{} {} to {}",
+        "Added".green(),
+        track.name.bold(),
+        playlist.name.bold()
+    );
+
+    Ok(())
+}
+
+pub async fn remove(backend: &impl PlayerBackend, name: &str) -> Result<()> {
+    let playlist = find(backend, name).await?;
+    let track = current_track(backend).await?;
+
+    let tracks = backend.playlist_tracks(&playlist.name).await?;
+    if !tracks.iter().any(|t| t.id == track.id) {
+        println!(
+            "{} is {} in {}",
+            track.name.bold(),
+            "not".yellow(),
+            playlist.name.bold()
+        );
+        return Ok(());
+    }
+
+    backend
+        .remove_from_playlist(&playlist.name, &track.id)
+        .await?;
+    println!(
+        "{} {} from {}",
+        "Removed".red(),
+        track.name.bold(),
+        playlist.name.bold()
+    );
+
+    Ok(())
+}
+
+#[expect(clippy::cast_possible_truncation)]
+fn to_m3u(playlist: &Playlist, tracks: &[Track]) -> Result<(String, usize)> {
+    let mut output = String::from("#EXTM3U\n");
+    writeln!(output, "#PLAYLIST:{}", playlist.name)?;
+
+    let mut skipped = 0;
+
+    for track in tracks {
+        let Some(location) = &track.location else {
+            skipped += 1;
+            continue;
+        };
+
+        writeln!(
+            output,
+            "#EXTINF:{},{} - {}",
+            track.duration as i32, track.artist, track.name
+        )?;
+        writeln!(output, "{}", location.display())?;
+    }
+
+    Ok((output, skipped))
+}
+
+fn to_json(playlist: &Playlist, tracks: &[Track]) -> Result<String> {
+    #[derive(serde::Serialize)]
+    struct Export<'a> {
+        #[serde(flatten)]
+        playlist: &'a Playlist,
+        tracks: &'a [Track],
+    }
+
+    let mut output = serde_json::to_string_pretty(&Export { playlist, tracks })?;
+    output.push('\n');
+
+    Ok(output)
+}
+
+/// Quote a CSV field if it contains a delimiter, a quote or a line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+fn to_csv(tracks: &[Track]) -> Result<String> {
+    let mut output = String::from("id,name,album,artist,duration,location\n");
+
+    for track in tracks {
+        let location = track
+            .location
+            .as_ref()
+            .map(|location| location.display().to_string())
+            .unwrap_or_default();
+
+        writeln!(
+            output,
+            "{},{},{},{},{},{}",
+            csv_field(&track.id),
+            csv_field(&track.name),
+            csv_field(&track.album),
+            csv_field(&track.artist),
+            track.duration,
+            csv_field(&location),
+        )?;
+    }
+
+    Ok(output)
+}
+
+pub async fn export(
+    backend: &impl PlayerBackend,
+    name: &str,
+    format: ExportFormat,
+    path: Option<&Path>,
+) -> Result<()> {
+    let playlist = find(backend, name).await?;
+    let tracks = backend.playlist_tracks(&playlist.name).await?;
+
+    let output = match format {
+        ExportFormat::M3u => {
+            let (output, skipped) = to_m3u(&playlist, &tracks)?;
+
+            if skipped > 0 {
+                eprintln!(
+                    "{} {skipped} track(s) that are not stored locally",
+                    "Skipped".yellow()
+                );
+            }
+
+            output
+        }
+        ExportFormat::Json => to_json(&playlist, &tracks)?,
+        ExportFormat::Csv => to_csv(&tracks)?,
+    };
+
+    if let Some(path) = path {
+        tokio::fs::write(path, output).await?;
+        eprintln!(
+            "{} {} to {}",
+            "Exported".green(),
+            playlist.name.bold(),
+            path.display()
+        );
+    } else {
+        print!("{output}");
+    }
+
+    Ok(())
+}
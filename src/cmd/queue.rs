@@ -0,0 +1,76 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use anstream::println;
+use chrono::{DateTime, Local, TimeDelta};
+use eyre::{Result, eyre};
+use owo_colors::OwoColorize as _;
+
+use crate::{
+    format,
+    music::{PlayerBackend, Track},
+};
+
+/// When each of `tracks` will finish playing if they are played back to back from `now`,
+/// after the `remaining` seconds of the current track.
+#[expect(clippy::cast_possible_truncation)]
+pub fn end_times(now: DateTime<Local>, remaining: f64, tracks: &[Track]) -> Vec<DateTime<Local>> {
+    let mut end = now + TimeDelta::milliseconds((remaining * 1000.) as i64);
+
+    tracks
+        .iter()
+        .map(|track| {
+            end += TimeDelta::milliseconds((track.duration * 1000.) as i64);
+            end
+        })
+        .collect()
+}
+
+#[expect(clippy::cast_possible_truncation)]
+pub async fn queue(backend: &impl PlayerBackend, limit: usize) -> Result<()> {
+    let track = backend
+        .current_track()
+        .await?
+        .ok_or_else(|| eyre!("Nothing is playing"))?;
+    let position = backend.player_position().await?.unwrap_or_default();
+
+    if let Some(playlist) = backend.current_playlist().await? {
+        println!("Up next in {}", playlist.name.bold());
+    }
+
+    // Music doesn't expose the shuffled order, so playlist order would be misleading
+    if backend.shuffle_enabled().await? {
+        println!(
+            "{}",
+            "Shuffle is on, so what plays next is unknown".yellow()
+        );
+        return Ok(());
+    }
+
+    let tracks = backend.upcoming_tracks(limit).await?;
+
+    if tracks.is_empty() {
+        println!("{} up next", "Nothing".red());
+        return Ok(());
+    }
+
+    let end_times = end_times(Local::now(), (track.duration - position).max(0.), &tracks);
+
+    for (index, (track, end)) in tracks.iter().zip(end_times).enumerate() {
+        println!(
+            "{} {} {}",
+            format!("{:>3}", index + 1).dimmed(),
+            track.name.bold(),
+            format!(
+                "{} · ends at {}",
+                format::format_duration_plain(track.duration as i32),
+                end.format("%H:%M")
+            )
+            .dimmed()
+        );
+        println!("    {} · {}", track.artist.blue(), track.album.magenta());
+    }
+
+    Ok(())
+}
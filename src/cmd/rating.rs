@@ -0,0 +1,75 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use anstream::println;
+use eyre::{Result, eyre};
+use owo_colors::OwoColorize as _;
+
+use crate::music::{PlayerBackend, Track};
+
+async fn current_track(backend: &impl PlayerBackend) -> Result<Track> {
+    backend
+        .current_track()
+        .await?
+        .ok_or_else(|| eyre!("Nothing is playing"))
+}
+
+pub async fn love(backend: &impl PlayerBackend) -> Result<()> {
+    let track = current_track(backend).await?;
+
+    // A track can't be both favorited and disliked
+    if track.disliked {
+        backend.set_disliked(false).await?;
+    }
+    backend.set_favorited(true).await?;
+
+    println!("{} {}", "Loved".red(), track.name.bold());
+    Ok(())
+}
+
+pub async fn unlove(backend: &impl PlayerBackend) -> Result<()> {
+    let track = current_track(backend).await?;
+
+    backend.set_favorited(false).await?;
+
+    println!("{} {}", "Unloved".yellow(), track.name.bold());
+    Ok(())
+}
+
+pub async fn dislike(backend: &impl PlayerBackend) -> Result<()> {
+    let track = current_track(backend).await?;
+
+    if track.favorited {
+        backend.set_favorited(false).await?;
+    }
+    backend.set_disliked(true).await?;
+
+    println!("{} {}", "Disliked".yellow(), track.name.bold());
+    Ok(())
+}
+
+pub async fn rate(backend: &impl PlayerBackend, rating: u8) -> Result<()> {
+    let track = current_track(backend).await?;
+
+    backend.set_rating(rating).await?;
+
+    if rating == 0 {
+        println!("{} rating of {}", "Cleared".yellow(), track.name.bold());
+    } else {
+        println!(
+            "{} {} {}",
+            "Rated".cyan(),
+            track.name.bold(),
+            stars(rating).yellow()
+        );
+    }
+
+    Ok(())
+}
+
+/// A rating out of 5 as filled and empty stars.
+pub fn stars(rating: u8) -> String {
+    let rating = usize::from(rating.min(5));
+    format!("{}{}", "★".repeat(rating), "☆".repeat(5 - rating))
+}
@@ -0,0 +1,101 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{io::Write as _, time::Duration};
+use tokio::{
+    io::{AsyncBufReadExt as _, BufReader},
+    process::Command,
+    signal, time,
+};
+
+use anstream::{eprintln, print, println};
+use eyre::{Result, bail};
+use owo_colors::OwoColorize as _;
+
+use crate::{
+    config::{Config, LastfmConfig},
+    history::Observation,
+    music::PlayerBackend,
+    scrobble::{self, Service as _, lastfm::LastFm},
+    state::State,
+};
+
+/// Authorize `am` to scrobble to a Last.fm account and save the session.
+pub async fn auth(config: &LastfmConfig) -> Result<()> {
+    let lastfm = LastFm::new(config)?;
+    let (token, url) = lastfm.request_token().await?;
+
+    println!("Authorize {} in your browser:", "am".bold());
+    println!("{}", url.as_str().underline());
+
+    // Not being able to open the browser is fine, the URL can be opened manually
+    let _ = Command::new("open").arg(url.as_str()).status().await;
+
+    print!("{} ", "Press Enter once you have authorized it".cyan());
+    std::io::stdout().flush()?;
+    BufReader::new(tokio::io::stdin())
+        .read_line(&mut String::new())
+        .await?;
+
+    let session = lastfm.create_session(&token).await?;
+    println!(
+        "{} in to Last.fm as {}",
+        "Logged".green(),
+        session.name.bold()
+    );
+
+    let mut state = State::load().await?;
+    state.lastfm_session = Some(session);
+    state.save().await?;
+
+    Ok(())
+}
+
+/// Scrobble to every service that is set up until interrupted.
+pub async fn scrobble(backend: &impl PlayerBackend, config: &Config) -> Result<()> {
+    let poll_interval = Duration::from_secs(config.lastfm.poll_interval_secs.get());
+    let mut scrobblers = scrobble::scrobblers(config, poll_interval, false).await?;
+
+    if scrobblers.is_empty() {
+        bail!("Set up Last.fm or ListenBrainz in the configuration file to scrobble");
+    }
+
+    for scrobbler in &scrobblers {
+        println!("{} to {}", "Scrobbling".green(), scrobbler.service().name());
+
+        if let Err(err) = scrobbler.flush().await {
+            eprintln!("{} {}", "Error".red(), err);
+        }
+    }
+
+    let mut intvl = time::interval(poll_interval);
+
+    loop {
+        tokio::select! {
+            _ = intvl.tick() => {
+                let observation = match Observation::poll(backend).await {
+                    Ok(observation) => observation,
+                    Err(err) => {
+                        eprintln!("{} {}", "Error".red(), err);
+                        continue;
+                    }
+                };
+
+                for scrobbler in &mut scrobblers {
+                    if let Err(err) = scrobbler.update(&observation).await {
+                        eprintln!("{} {}", "Error".red(), err);
+                    }
+                }
+            }
+
+            _ = signal::ctrl_c() => {
+                break;
+            }
+        }
+    }
+
+    println!("{} scrobbler", "Shutting down".yellow());
+
+    Ok(())
+}
@@ -0,0 +1,283 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::io::{IsTerminal as _, Write as _};
+
+use anstream::{print, println};
+use eyre::{Result, bail, eyre};
+use owo_colors::OwoColorize as _;
+use tokio::io::{AsyncBufReadExt as _, BufReader};
+
+use crate::{
+    format,
+    music::{PlayerBackend, Playlist, Track},
+};
+
+/// How many library tracks to consider when ranking results.
+const TRACK_SEARCH_LIMIT: usize = 200;
+/// How many results to offer when choosing one to play.
+const CHOICE_LIMIT: usize = 10;
+
+#[derive(Debug, Clone)]
+pub enum SearchResult {
+    Track(Track),
+    Album { name: String, artist: String },
+    Artist { name: String },
+    Playlist(Playlist),
+}
+
+impl SearchResult {
+    /// Tie-breaker between results that match the query equally well.
+    fn kind_order(&self) -> u8 {
+        match self {
+            Self::Track(_) => 0,
+            Self::Album { .. } => 1,
+            Self::Artist { .. } => 2,
+            Self::Playlist(_) => 3,
+        }
+    }
+
+    /// Whether the result can be played as a whole, which albums and artists can't, as Music
+    /// can only play a single track or playlist.
+    fn is_playable(&self) -> bool {
+        matches!(self, Self::Track(_) | Self::Playlist(_))
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Self::Track(track) => &track.name,
+            Self::Album { name, .. } | Self::Artist { name, .. } => name,
+            Self::Playlist(playlist) => &playlist.name,
+        }
+    }
+}
+
+/// How well `text` matches `query`: 3 for an exact match, 2 for a prefix,
+/// 1 for a substring and 0 otherwise, ignoring case.
+fn match_score(text: &str, query: &str) -> u8 {
+    let text = text.to_lowercase();
+    let query = query.to_lowercase();
+
+    if text == query {
+        3
+    } else if text.starts_with(&query) {
+        2
+    } else {
+        u8::from(text.contains(&query))
+    }
+}
+
+/// Group matching tracks into track, album and artist results, add playlists, and rank
+/// everything by how well its name matches `query`.
+fn rank(query: &str, tracks: &[Track], playlists: Vec<Playlist>) -> Vec<SearchResult> {
+    let mut albums: Vec<&Track> = Vec::new();
+    let mut artists: Vec<&Track> = Vec::new();
+
+    for track in tracks {
+        if match_score(&track.album, query) > 0
+            && !albums
+                .iter()
+                .any(|t| t.album == track.album && t.artist == track.artist)
+        {
+            albums.push(track);
+        }
+
+        if match_score(&track.artist, query) > 0
+            && !artists.iter().any(|t| t.artist == track.artist)
+        {
+            artists.push(track);
+        }
+    }
+
+    let albums = albums.into_iter().map(|track| SearchResult::Album {
+        name: track.album.clone(),
+        artist: track.artist.clone(),
+    });
+    let artists = artists.into_iter().map(|track| SearchResult::Artist {
+        name: track.artist.clone(),
+    });
+
+    let mut results = tracks
+        .iter()
+        .cloned()
+        .map(SearchResult::Track)
+        .chain(albums)
+        .chain(artists)
+        .chain(playlists.into_iter().map(SearchResult::Playlist))
+        .collect::<Vec<_>>();
+
+    results.sort_by_key(|result| {
+        (
+            std::cmp::Reverse(match_score(result.name(), query)),
+            result.kind_order(),
+        )
+    });
+
+    results
+}
+
+/// Print a track on two lines, preceded by its 0-based `index` in a list.
+#[expect(clippy::cast_possible_truncation)]
+pub fn print_track(index: usize, track: &Track) {
+    println!(
+        "{} {} {}",
+        format!("{:>3}", index + 1).dimmed(),
+        track.name.bold(),
+        format::format_duration_plain(track.duration as i32).dimmed()
+    );
+    println!("    {} · {}", track.artist.blue(), track.album.magenta());
+}
+
+fn print_result(index: usize, result: &SearchResult) {
+    let label = format!("{:>3}", index + 1);
+
+    match result {
+        SearchResult::Track(track) => print_track(index, track),
+        SearchResult::Album { name, artist, .. } => {
+            println!(
+                "{} {} {}",
+                label.dimmed(),
+                name.magenta().bold(),
+                "album".dimmed()
+            );
+            println!("    {}", artist.blue());
+        }
+        SearchResult::Artist { name, .. } => {
+            println!(
+                "{} {} {}",
+                label.dimmed(),
+                name.blue().bold(),
+                "artist".dimmed()
+            );
+        }
+        SearchResult::Playlist(playlist) => {
+            println!(
+                "{} {} {}",
+                label.dimmed(),
+                playlist.name.bold(),
+                format!(
+                    "playlist · {}",
+                    format::format_duration_plain(playlist.duration)
+                )
+                .dimmed()
+            );
+        }
+    }
+}
+
+async fn find(backend: &impl PlayerBackend, query: &str) -> Result<Vec<SearchResult>> {
+    let tracks = backend.search_tracks(query, TRACK_SEARCH_LIMIT).await?;
+    let playlists = backend.search_playlists(query).await?;
+
+    Ok(rank(query, &tracks, playlists))
+}
+
+pub async fn search(backend: &impl PlayerBackend, query: &str, limit: usize) -> Result<()> {
+    let results = find(backend, query).await?;
+
+    if results.is_empty() {
+        println!("{} results for {}", "No".red(), query.bold());
+    }
+
+    for (index, result) in results.iter().take(limit).enumerate() {
+        print_result(index, result);
+    }
+
+    Ok(())
+}
+
+async fn play_result(backend: &impl PlayerBackend, result: &SearchResult) -> Result<()> {
+    match result {
+        SearchResult::Track(track) => backend.play_track(&track.id).await,
+        SearchResult::Playlist(playlist) => backend.play_playlist(&playlist.name).await,
+        SearchResult::Album { .. } | SearchResult::Artist { .. } => {
+            bail!("Only tracks and playlists can be played")
+        }
+    }
+}
+
+/// Search for `query` and play a matching track or playlist, either the top one if `first` is
+/// set or one chosen interactively.
+pub async fn play_query(backend: &impl PlayerBackend, query: &str, first: bool) -> Result<()> {
+    let mut results = find(backend, query).await?;
+    results.retain(SearchResult::is_playable);
+
+    if results.is_empty() {
+        bail!("No tracks or playlists match {query:?}");
+    }
+
+    let result = if first || results.len() == 1 {
+        &results[0]
+    } else {
+        if !std::io::stdin().is_terminal() {
+            bail!("Multiple results for {query:?}; use --first to play the top result");
+        }
+
+        let results = &results[..CHOICE_LIMIT.min(results.len())];
+
+        for (index, result) in results.iter().enumerate() {
+            print_result(index, result);
+        }
+
+        print!("{} ", format!("Play [1-{}]:", results.len()).cyan());
+        std::io::stdout().flush()?;
+
+        let mut line = String::new();
+        BufReader::new(tokio::io::stdin())
+            .read_line(&mut line)
+            .await?;
+
+        let choice = line
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .filter(|choice| (1..=results.len()).contains(choice))
+            .ok_or_else(|| eyre!("Invalid choice {:?}", line.trim()))?;
+
+        &results[choice - 1]
+    };
+
+    play_result(backend, result).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music::fake::{ScriptedBackend, Snapshot, track};
+
+    fn on_album(id: &str, name: &str, album: &str) -> Track {
+        Track {
+            album: album.to_owned(),
+            ..track(id, name)
+        }
+    }
+
+    #[test]
+    fn ranks_exact_matches_first() {
+        let tracks = [
+            on_album("1", "Something", "Abbey Road"),
+            on_album("2", "Abbey Road Medley", "Live"),
+        ];
+        let results = rank("abbey road", &tracks, Vec::new());
+
+        assert!(matches!(&results[0], SearchResult::Album { name, .. } if name == "Abbey Road"));
+        assert!(matches!(&results[1], SearchResult::Track(track) if track.id == "2"));
+    }
+
+    #[tokio::test]
+    async fn plays_tracks_rather_than_albums() {
+        let backend = ScriptedBackend::new(Snapshot::stopped()).library(
+            vec![
+                on_album("1", "Something", "Abbey Road"),
+                on_album("2", "Abbey Road Medley", "Live"),
+            ],
+            Vec::new(),
+        );
+
+        play_query(&backend, "Abbey Road", true).await.unwrap();
+
+        assert_eq!(backend.commands(), ["play track"]);
+        assert_eq!(backend.snapshot().track.unwrap().id, "2");
+    }
+}
@@ -0,0 +1,146 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use anstream::println;
+use eyre::{Result, eyre};
+use owo_colors::OwoColorize as _;
+
+use crate::{format, music::PlayerBackend};
+
+/// A position to seek to in the current track.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SeekTarget {
+    /// An absolute position in seconds
+    Absolute(f64),
+    /// An offset from the current position in seconds
+    Relative(f64),
+    /// A percentage of the track's duration
+    Percent(f64),
+}
+
+impl std::str::FromStr for SeekTarget {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || {
+            eyre!(
+                "expected a position like 1:23, 1m23s or 83, an offset like +15s or -30, or a percentage like 50%"
+            )
+        };
+
+        if let Some(percent) = s.strip_suffix('%') {
+            return percent
+                .parse::<f64>()
+                .ok()
+                .filter(|p| (0.0..=100.0).contains(p))
+                .map(Self::Percent)
+                .ok_or_else(invalid);
+        }
+
+        if let Some(offset) = s.strip_prefix('+') {
+            return format::parse_duration(offset)
+                .map(Self::Relative)
+                .ok_or_else(invalid);
+        }
+
+        if let Some(offset) = s.strip_prefix('-') {
+            return format::parse_duration(offset)
+                .map(|offset| Self::Relative(-offset))
+                .ok_or_else(invalid);
+        }
+
+        format::parse_duration(s)
+            .map(Self::Absolute)
+            .ok_or_else(invalid)
+    }
+}
+
+/// Seek to `target`, clamped to the current track, returning the new position and the
+/// track's duration in seconds.
+pub async fn seek_to(backend: &impl PlayerBackend, target: SeekTarget) -> Result<(f64, f64)> {
+    let track = backend
+        .current_track()
+        .await?
+        .ok_or_else(|| eyre!("Nothing is playing"))?;
+
+    let position = match target {
+        SeekTarget::Absolute(position) => position,
+        SeekTarget::Relative(offset) => {
+            backend
+                .player_position()
+                .await?
+                .ok_or_else(|| eyre!("Could not obtain player position"))?
+                + offset
+        }
+        SeekTarget::Percent(percent) => track.duration * percent / 100.,
+    }
+    .clamp(0., track.duration);
+
+    backend.set_player_position(position).await?;
+
+    Ok((position, track.duration))
+}
+
+#[expect(clippy::cast_possible_truncation)]
+pub async fn seek(backend: &impl PlayerBackend, target: SeekTarget) -> Result<()> {
+    let (position, duration) = seek_to(backend, target).await?;
+
+    println!(
+        "{} to {} of {}",
+        "Seeked".cyan(),
+        format::format_duration(position as i32, false),
+        format::format_duration(duration as i32, true),
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music::fake::{ScriptedBackend, Snapshot, track};
+
+    #[test]
+    fn parses_targets() {
+        let cases = [
+            ("1:23", Some(SeekTarget::Absolute(83.))),
+            ("+15s", Some(SeekTarget::Relative(15.))),
+            ("-1m", Some(SeekTarget::Relative(-60.))),
+            ("50%", Some(SeekTarget::Percent(50.))),
+            ("150%", None),
+            ("+-5", None),
+            ("soon", None),
+        ];
+
+        for (s, expected) in cases {
+            assert_eq!(s.parse::<SeekTarget>().ok(), expected, "{s:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn clamps_to_the_track() {
+        // Three minutes long
+        let backend = ScriptedBackend::new(Snapshot::playing(track("1", "One")).position(170.));
+
+        assert_eq!(
+            seek_to(&backend, SeekTarget::Relative(30.)).await.unwrap(),
+            (180., 180.)
+        );
+        assert_eq!(
+            seek_to(&backend, SeekTarget::Relative(-300.))
+                .await
+                .unwrap(),
+            (0., 180.)
+        );
+        assert_eq!(
+            seek_to(&backend, SeekTarget::Absolute(600.)).await.unwrap(),
+            (180., 180.)
+        );
+        assert_eq!(
+            seek_to(&backend, SeekTarget::Percent(50.)).await.unwrap(),
+            (90., 180.)
+        );
+        assert_eq!(backend.player_position().await.unwrap(), Some(90.));
+    }
+}
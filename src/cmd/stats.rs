@@ -0,0 +1,356 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::{BTreeSet, HashMap};
+
+use anstream::println;
+use chrono::{Local, NaiveDate, TimeDelta, Timelike as _};
+use clap::ValueEnum;
+use eyre::Result;
+use owo_colors::OwoColorize as _;
+use serde::Serialize;
+
+use crate::{
+    format,
+    history::{self, Play},
+};
+
+use super::BAR_CHAR;
+
+/// How many tracks, artists and albums to list.
+const TOP_LIMIT: usize = 5;
+/// Width of the longest bar in the hour of day histogram.
+const HISTOGRAM_WIDTH: f64 = 30.;
+
+#[derive(ValueEnum, Serialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Period {
+    /// The last 7 days
+    #[default]
+    Week,
+    /// The last 30 days
+    Month,
+    /// The last 365 days
+    Year,
+    /// Everything in the history
+    All,
+}
+
+impl Period {
+    fn length(self) -> Option<TimeDelta> {
+        match self {
+            Self::Week => Some(TimeDelta::days(7)),
+            Self::Month => Some(TimeDelta::days(30)),
+            Self::Year => Some(TimeDelta::days(365)),
+            Self::All => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Week => "the last week",
+            Self::Month => "the last month",
+            Self::Year => "the last year",
+            Self::All => "all time",
+        }
+    }
+}
+
+/// A track, artist or album and how much it was played.
+#[derive(Serialize, Debug, Clone)]
+pub struct Top {
+    pub name: String,
+    /// The artist of a track or album
+    pub artist: Option<String>,
+    pub plays: usize,
+    /// Seconds listened
+    pub time: f64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Stats {
+    pub period: Period,
+    pub plays: usize,
+    /// Seconds listened
+    pub time: f64,
+    /// Fraction of plays that were skipped, from 0 to 1
+    pub skip_rate: f64,
+    /// Consecutive days with plays, up to today or yesterday
+    pub current_streak: usize,
+    pub longest_streak: usize,
+    pub top_tracks: Vec<Top>,
+    pub top_artists: Vec<Top>,
+    pub top_albums: Vec<Top>,
+    /// Seconds listened in each hour of the day, from midnight
+    pub by_hour: [f64; 24],
+}
+
+/// The most played of `plays` grouped by `key`, which gives a name and an optional artist.
+fn top(plays: &[Play], key: impl Fn(&Play) -> (&str, Option<&str>)) -> Vec<Top> {
+    let mut groups = HashMap::<(&str, Option<&str>), (usize, f64)>::new();
+
+    for play in plays {
+        let group = groups.entry(key(play)).or_default();
+        group.0 += 1;
+        group.1 += play.played;
+    }
+
+    let mut top = groups
+        .into_iter()
+        .map(|((name, artist), (plays, time))| Top {
+            name: name.to_owned(),
+            artist: artist.map(str::to_owned),
+            plays,
+            time,
+        })
+        .collect::<Vec<_>>();
+
+    top.sort_by(|a, b| b.plays.cmp(&a.plays).then(b.time.total_cmp(&a.time)));
+    top.truncate(TOP_LIMIT);
+
+    top
+}
+
+/// The current and longest runs of consecutive days in `days`. The current streak still
+/// counts if nothing has been played yet `today`.
+fn streaks(days: &BTreeSet<NaiveDate>, today: NaiveDate) -> (usize, usize) {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+
+    for &day in days {
+        run = if previous.and_then(|p| p.succ_opt()) == Some(day) {
+            run + 1
+        } else {
+            1
+        };
+        longest = longest.max(run);
+        previous = Some(day);
+    }
+
+    let mut current = 0;
+    let mut day = if days.contains(&today) {
+        Some(today)
+    } else {
+        today.pred_opt()
+    };
+
+    while let Some(d) = day.filter(|d| days.contains(d)) {
+        current += 1;
+        day = d.pred_opt();
+    }
+
+    (current, longest)
+}
+
+#[expect(clippy::cast_precision_loss)]
+pub fn compute(plays: &[Play], period: Period, today: NaiveDate) -> Stats {
+    let mut by_hour = [0.; 24];
+    for play in plays {
+        by_hour[play.started_at.hour() as usize] += play.played;
+    }
+
+    let skipped = plays.iter().filter(|play| play.skipped).count();
+    let days = plays
+        .iter()
+        .map(|play| play.started_at.date_naive())
+        .collect::<BTreeSet<_>>();
+    let (current_streak, longest_streak) = streaks(&days, today);
+
+    Stats {
+        period,
+        plays: plays.len(),
+        time: plays.iter().map(|play| play.played).sum(),
+        skip_rate: if plays.is_empty() {
+            0.
+        } else {
+            skipped as f64 / plays.len() as f64
+        },
+        current_streak,
+        longest_streak,
+        top_tracks: top(plays, |play| (&play.name, Some(&play.artist))),
+        top_artists: top(plays, |play| (&play.artist, None)),
+        top_albums: top(plays, |play| (&play.album, Some(&play.artist))),
+        by_hour,
+    }
+}
+
+fn days(count: usize) -> String {
+    format!("{count} {}", if count == 1 { "day" } else { "days" })
+}
+
+fn print_top(title: &str, top: &[Top]) {
+    if top.is_empty() {
+        return;
+    }
+
+    println!();
+    println!("{}", title.bold());
+
+    for (index, entry) in top.iter().enumerate() {
+        let artist = entry
+            .artist
+            .as_ref()
+            .map(|artist| format!(" · {}", artist.blue()))
+            .unwrap_or_default();
+
+        println!(
+            "{} {}{artist} {}",
+            format!("{:>3}", index + 1).dimmed(),
+            entry.name,
+            format!(
+                "{} {}",
+                entry.plays,
+                if entry.plays == 1 { "play" } else { "plays" }
+            )
+            .dimmed()
+        );
+    }
+}
+
+#[expect(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn print_stats(stats: &Stats) {
+    println!("Listening stats for {}", stats.period.label().bold());
+    println!();
+    println!(
+        "{} {}",
+        "Listening time".dimmed(),
+        format::format_duration(stats.time as i32, false)
+    );
+    println!(
+        "{} {} · {} skipped",
+        "Plays".dimmed(),
+        stats.plays,
+        format!("{:.0}%", stats.skip_rate * 100.).yellow()
+    );
+    println!(
+        "{} {} · longest {}",
+        "Streak".dimmed(),
+        days(stats.current_streak).green(),
+        days(stats.longest_streak)
+    );
+
+    print_top("Top tracks", &stats.top_tracks);
+    print_top("Top artists", &stats.top_artists);
+    print_top("Top albums", &stats.top_albums);
+
+    let max = stats.by_hour.iter().copied().fold(0., f64::max);
+    if max > 0. {
+        println!();
+        println!("{}", "By hour of day".bold());
+
+        for (hour, &time) in stats.by_hour.iter().enumerate() {
+            let bar = BAR_CHAR.repeat((time / max * HISTOGRAM_WIDTH).round() as usize);
+            println!(
+                "{} {} {}",
+                format!("{hour:02}").dimmed(),
+                bar.cyan(),
+                format::format_duration_plain(time as i32).dimmed()
+            );
+        }
+    }
+}
+
+pub async fn stats(period: Period, json: bool) -> Result<()> {
+    let now = Local::now();
+    let plays = history::read(period.length().map(|length| now - length)).await?;
+    let stats = compute(&plays, period, now.date_naive());
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+    } else if plays.is_empty() {
+        println!("{} listening history for {}", "No".red(), period.label());
+    } else {
+        print_stats(&stats);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone as _;
+
+    use super::*;
+
+    fn play(name: &str, artist: &str, started_at: (u32, u32), played: f64, skipped: bool) -> Play {
+        let (day, hour) = started_at;
+        let started_at = Local.with_ymd_and_hms(2025, 3, day, hour, 0, 0).unwrap();
+
+        Play {
+            track_id: name.to_owned(),
+            name: name.to_owned(),
+            album: format!("{name} - Single"),
+            artist: artist.to_owned(),
+            duration: 200.,
+            started_at,
+            ended_at: started_at + TimeDelta::seconds(200),
+            played,
+            skipped,
+        }
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, day).unwrap()
+    }
+
+    #[test]
+    fn computes_totals_and_top_lists() {
+        let plays = [
+            play("One", "Alice", (1, 9), 200., false),
+            play("Two", "Bob", (1, 9), 30., true),
+            play("One", "Alice", (2, 21), 200., false),
+            play("Three", "Alice", (2, 21), 100., true),
+        ];
+
+        let stats = compute(&plays, Period::Week, date(2));
+
+        assert_eq!(stats.plays, 4);
+        assert!((stats.time - 530.).abs() < f64::EPSILON);
+        assert!((stats.skip_rate - 0.5).abs() < f64::EPSILON);
+
+        assert_eq!(stats.top_tracks[0].name, "One");
+        assert_eq!(stats.top_tracks[0].artist.as_deref(), Some("Alice"));
+        assert_eq!(stats.top_tracks[0].plays, 2);
+        // Ties are broken by time listened
+        assert_eq!(stats.top_tracks[1].name, "Three");
+
+        assert_eq!(stats.top_artists[0].name, "Alice");
+        assert_eq!(stats.top_artists[0].artist, None);
+        assert_eq!(stats.top_artists[0].plays, 3);
+        assert_eq!(stats.top_albums[0].name, "One - Single");
+
+        assert!((stats.by_hour[9] - 230.).abs() < f64::EPSILON);
+        assert!((stats.by_hour[21] - 300.).abs() < f64::EPSILON);
+        assert_eq!(stats.by_hour.iter().filter(|&&time| time > 0.).count(), 2);
+    }
+
+    #[test]
+    fn computes_streaks() {
+        let plays = [
+            play("One", "Alice", (1, 9), 200., false),
+            play("One", "Alice", (2, 9), 200., false),
+            play("One", "Alice", (3, 9), 200., false),
+            play("One", "Alice", (6, 9), 200., false),
+            play("One", "Alice", (7, 9), 200., false),
+        ];
+
+        // Still going if nothing was played today
+        let stats = compute(&plays, Period::All, date(8));
+        assert_eq!((stats.current_streak, stats.longest_streak), (2, 3));
+
+        let stats = compute(&plays, Period::All, date(9));
+        assert_eq!((stats.current_streak, stats.longest_streak), (0, 3));
+    }
+
+    #[test]
+    fn handles_an_empty_history() {
+        let stats = compute(&[], Period::Month, date(1));
+
+        assert_eq!(stats.plays, 0);
+        assert!(stats.skip_rate.abs() < f64::EPSILON);
+        assert_eq!((stats.current_streak, stats.longest_streak), (0, 0));
+        assert!(stats.top_tracks.is_empty());
+    }
+}
@@ -0,0 +1,113 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use anstream::println;
+use eyre::{Result, eyre};
+use owo_colors::OwoColorize as _;
+
+use crate::{music::PlayerBackend, state::State};
+
+/// A change to the player volume.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VolumeChange {
+    /// Set the volume to an absolute level
+    Set(u8),
+    /// Raise or lower the volume by a number of steps
+    Adjust(i16),
+    /// Set the volume to 0, remembering the current level
+    Mute,
+    /// Restore the level from before muting
+    Unmute,
+}
+
+impl std::str::FromStr for VolumeChange {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let change = match s {
+            "mute" => Some(Self::Mute),
+            "unmute" => Some(Self::Unmute),
+            _ if s.starts_with(['+', '-']) => s.parse().ok().map(Self::Adjust),
+            _ => s.parse().ok().filter(|l| *l <= 100).map(Self::Set),
+        };
+
+        change.ok_or_else(|| {
+            eyre!("expected a volume from 0 to 100, a relative change like +5 or -5, `mute` or `unmute`")
+        })
+    }
+}
+
+fn print_volume(verb: &str, volume: u8) {
+    println!("{} volume to {}", verb.cyan(), format!("{volume}%").bold());
+}
+
+pub async fn volume(backend: &impl PlayerBackend, change: Option<VolumeChange>) -> Result<()> {
+    // Changes relative to the current volume need it as it is now
+    if matches!(
+        change,
+        Some(VolumeChange::Adjust(_) | VolumeChange::Mute | VolumeChange::Unmute)
+    ) {
+        backend.refresh().await?;
+    }
+
+    let current = backend.volume().await?;
+
+    let Some(change) = change else {
+        println!("Volume is {}", format!("{current}%").bold());
+        return Ok(());
+    };
+
+    let mut state = State::load().await?;
+
+    match change {
+        VolumeChange::Set(level) => {
+            backend.set_volume(level).await?;
+            print_volume("Set", level);
+        }
+
+        VolumeChange::Adjust(steps) => {
+            let level = (i16::from(current) + steps).clamp(0, 100);
+            let level = u8::try_from(level)?;
+
+            backend.set_volume(level).await?;
+            print_volume(if steps < 0 { "Lowered" } else { "Raised" }, level);
+        }
+
+        VolumeChange::Mute => {
+            if state.muted_volume.is_some() && current == 0 {
+                println!("{} muted", "Already".yellow());
+                return Ok(());
+            }
+
+            state.muted_volume = Some(current);
+            state.save().await?;
+
+            backend.set_volume(0).await?;
+            println!("{} volume (was {}%)", "Muted".red(), current);
+            return Ok(());
+        }
+
+        VolumeChange::Unmute => {
+            let Some(level) = state.muted_volume.filter(|_| current == 0) else {
+                // The volume was changed from elsewhere since muting
+                if state.muted_volume.take().is_some() {
+                    state.save().await?;
+                }
+
+                println!("{} muted", "Not".yellow());
+                return Ok(());
+            };
+
+            backend.set_volume(level).await?;
+            print_volume("Restored", level);
+        }
+    }
+
+    // Any explicit change supersedes the level remembered when muting
+    if state.muted_volume.take().is_some() {
+        state.save().await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,439 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{num::NonZeroU64, path::PathBuf};
+
+use anstream::eprintln;
+use eyre::{Result, WrapErr as _};
+use owo_colors::OwoColorize as _;
+use serde::{Deserialize, Deserializer, Serialize, de::Error as _};
+use toml::de::{DeTable, DeValue};
+
+use crate::{artwork::Graphics, dirs, events::EventKind};
+
+/// Configuration file contents. Every key is optional; CLI flags take precedence.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub now: NowConfig,
+    pub discord: DiscordConfig,
+    pub metadata: MetadataConfig,
+    pub lyrics: LyricsConfig,
+    pub history: HistoryConfig,
+    pub daemon: DaemonConfig,
+    pub lastfm: LastfmConfig,
+    pub listenbrainz: ListenBrainzConfig,
+    pub hooks: Vec<HookConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NowConfig {
+    /// Use Nerd Font symbols
+    pub nerd_fonts: bool,
+    /// Playback progress bar width
+    pub bar_width: Option<i32>,
+    /// Show album artwork, and how to draw it
+    pub artwork: Option<Graphics>,
+    /// Show a lyrics pane in the full-screen UI
+    pub lyrics: bool,
+    /// Show a pane with the tracks up next in the full-screen UI
+    pub queue: bool,
+    /// How often to query Music for the playback state, in milliseconds
+    pub poll_interval_ms: NonZeroU64,
+    /// How often to advance the displayed position between polls, in milliseconds
+    pub tick_interval_ms: NonZeroU64,
+}
+
+impl Default for NowConfig {
+    fn default() -> Self {
+        Self {
+            nerd_fonts: true,
+            bar_width: None,
+            artwork: None,
+            lyrics: false,
+            queue: false,
+            poll_interval_ms: NonZeroU64::new(5000).unwrap(),
+            tick_interval_ms: NonZeroU64::new(250).unwrap(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiscordConfig {
+    /// Discord application ID to show the presence as
+    pub client_id: String,
+    /// How often to update the presence, in seconds
+    pub poll_interval_secs: NonZeroU64,
+}
+
+impl Default for DiscordConfig {
+    fn default() -> Self {
+        Self {
+            client_id: "861702238472241162".into(),
+            poll_interval_secs: NonZeroU64::new(5).unwrap(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetadataConfig {
+    /// Apple Music storefront (country code) to search the catalog in
+    pub storefront: String,
+    /// How long to cache the metadata found for a track, in days
+    pub cache_ttl_days: u32,
+    /// How long to remember that a track isn't in the catalog, in hours
+    pub negative_cache_ttl_hours: u32,
+    /// How many tracks to cache metadata for, or 0 to disable the cache
+    pub cache_size: usize,
+    /// How confident a match in the catalog has to be, from 0 to 1, to be used
+    #[serde(deserialize_with = "unit_interval")]
+    pub min_confidence: f64,
+}
+
+impl Default for MetadataConfig {
+    fn default() -> Self {
+        Self {
+            storefront: "us".into(),
+            cache_ttl_days: 30,
+            negative_cache_ttl_hours: 24,
+            cache_size: 1000,
+            min_confidence: 0.6,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LyricsConfig {
+    /// Directory of `Artist - Title.lrc` or `Title.lrc` files, searched when there is no
+    /// `.lrc` file next to the track
+    pub dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryConfig {
+    /// Record listening history while `am daemon` or `am discord` is running
+    pub enabled: bool,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DaemonConfig {
+    /// How often to query Music for the playback state, in seconds
+    pub poll_interval_secs: NonZeroU64,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval_secs: NonZeroU64::new(5).unwrap(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LastfmConfig {
+    /// API key of a Last.fm API account, from <https://www.last.fm/api/account/create>
+    pub api_key: String,
+    /// Shared secret of the Last.fm API account
+    pub api_secret: String,
+    /// Last.fm API endpoint, which may be a local `http://` server for testing
+    pub api_url: String,
+    /// Page where users authorize the API account
+    pub auth_url: String,
+    /// Also scrobble while `am discord` is running
+    pub discord: bool,
+    /// How often `am scrobble` checks what is playing, in seconds
+    pub poll_interval_secs: NonZeroU64,
+}
+
+impl Default for LastfmConfig {
+    fn default() -> Self {
+        Self {
+            api_key: String::new(),
+            api_secret: String::new(),
+            api_url: "https://ws.audioscrobbler.com/2.0/".into(),
+            auth_url: "https://www.last.fm/api/auth/".into(),
+            discord: false,
+            poll_interval_secs: NonZeroU64::new(5).unwrap(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ListenBrainzConfig {
+    /// User token from <https://listenbrainz.org/settings/>
+    pub token: String,
+    /// Root of the ListenBrainz API, which may be a self-hosted instance or a local
+    /// `http://` server for testing
+    pub api_url: String,
+    /// Also submit listens while `am discord` is running
+    pub discord: bool,
+}
+
+impl Default for ListenBrainzConfig {
+    fn default() -> Self {
+        Self {
+            token: String::new(),
+            api_url: "https://api.listenbrainz.org/".into(),
+            discord: false,
+        }
+    }
+}
+
+/// A command to run or a URL to notify when something happens during playback.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HookConfig {
+    /// Events to run the hook for, or all of `track_changed`, `state_changed` and `quit`
+    /// if empty
+    #[serde(deserialize_with = "hook_events")]
+    pub events: Vec<EventKind>,
+    /// Shell command to run, with the event in `AM_`-prefixed environment variables
+    pub command: Option<String>,
+    /// URL to POST the event to as JSON, which may be plain HTTP
+    pub url: Option<String>,
+    /// How long the hook may take before it is stopped, in seconds
+    pub timeout_secs: NonZeroU64,
+}
+
+fn unit_interval<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    let value = f64::deserialize(deserializer)?;
+
+    if !(0. ..=1.).contains(&value) {
+        return Err(D::Error::custom("expected a number from 0 to 1"));
+    }
+
+    Ok(value)
+}
+
+/// The events of a hook, rejecting the ones that only `am events` reports.
+fn hook_events<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<EventKind>, D::Error> {
+    let events = Vec::<EventKind>::deserialize(deserializer)?;
+
+    if events.iter().any(|event| !event.is_hookable()) {
+        return Err(D::Error::custom(
+            "hooks only run for `track_changed`, `state_changed` and `quit` events",
+        ));
+    }
+
+    Ok(events)
+}
+
+impl Default for HookConfig {
+    fn default() -> Self {
+        Self {
+            events: Vec::new(),
+            command: None,
+            url: None,
+            timeout_secs: NonZeroU64::new(10).unwrap(),
+        }
+    }
+}
+
+/// A key present in the configuration file that `am` does not recognize.
+#[derive(Debug, Clone)]
+pub struct UnknownKey {
+    pub key: String,
+    pub line: Option<usize>,
+}
+
+impl std::fmt::Display for UnknownKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "`{}` (line {line})", self.key),
+            None => write!(f, "`{}`", self.key),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+fn path_segments(path: &serde_ignored::Path) -> Vec<Segment> {
+    use serde_ignored::Path;
+
+    match path {
+        Path::Root => Vec::new(),
+        Path::Seq { parent, index } => {
+            let mut segments = path_segments(parent);
+            segments.push(Segment::Index(*index));
+            segments
+        }
+        Path::Map { parent, key } => {
+            let mut segments = path_segments(parent);
+            segments.push(Segment::Key(key.clone()));
+            segments
+        }
+        Path::Some { parent }
+        | Path::NewtypeStruct { parent }
+        | Path::NewtypeVariant { parent } => path_segments(parent),
+    }
+}
+
+/// Find the line on which the key at `segments` is defined.
+fn locate(source: &str, segments: &[Segment]) -> Option<usize> {
+    let root = DeValue::Table(DeTable::parse(source).ok()?.into_inner());
+    let mut value = &root;
+    let mut offset = None;
+
+    for segment in segments {
+        value = match (segment, value) {
+            (Segment::Key(name), DeValue::Table(table)) => {
+                let (key, value) = table.iter().find(|(key, _)| key.get_ref() == name)?;
+                offset = Some(key.span().start);
+                value.get_ref()
+            }
+            (Segment::Index(index), DeValue::Array(array)) => {
+                let value = array.get(*index)?;
+                offset = Some(value.span().start);
+                value.get_ref()
+            }
+            _ => return None,
+        };
+    }
+
+    offset.map(|offset| line_of(source, offset))
+}
+
+fn line_of(source: &str, offset: usize) -> usize {
+    source[..offset].matches('\n').count() + 1
+}
+
+impl Config {
+    /// The configuration file path, from `$AM_CONFIG` or `$XDG_CONFIG_HOME/am/config.toml`.
+    pub fn path() -> Result<PathBuf> {
+        if let Some(path) = std::env::var_os("AM_CONFIG").filter(|path| !path.is_empty()) {
+            return Ok(PathBuf::from(path));
+        }
+
+        Ok(dirs::config_dir()?.join("config.toml"))
+    }
+
+    /// Parse a configuration file, collecting any keys that are not recognized.
+    ///
+    /// Syntax and type errors include the line and column they occurred at.
+    pub fn parse(source: &str) -> Result<(Self, Vec<UnknownKey>), toml::de::Error> {
+        let mut unknown = Vec::new();
+
+        let config = serde_ignored::deserialize(toml::Deserializer::parse(source)?, |path| {
+            unknown.push(path_segments(&path));
+        })?;
+
+        let mut unknown = unknown
+            .into_iter()
+            .map(|segments| UnknownKey {
+                key: segments
+                    .iter()
+                    .map(|segment| match segment {
+                        Segment::Key(key) => key.clone(),
+                        Segment::Index(index) => index.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("."),
+                line: locate(source, &segments),
+            })
+            .collect::<Vec<_>>();
+
+        unknown.sort_by_key(|key| key.line);
+
+        Ok((config, unknown))
+    }
+
+    /// Read the configuration file, returning the parsed configuration and any unknown keys,
+    /// or `None` if it does not exist.
+    pub fn read() -> Result<Option<(Self, Vec<UnknownKey>)>> {
+        let path = Self::path()?;
+
+        let source = match std::fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                return Err(err).wrap_err_with(|| format!("failed to read {}", path.display()));
+            }
+        };
+
+        let parsed = Self::parse(&source)
+            .wrap_err_with(|| format!("invalid configuration in {}", path.display()))?;
+        Ok(Some(parsed))
+    }
+
+    /// Load the configuration, warning about unknown keys and falling back to defaults
+    /// if there is no configuration file.
+    pub fn load() -> Result<Self> {
+        let Some((config, unknown)) = Self::read()? else {
+            return Ok(Self::default());
+        };
+
+        for key in unknown {
+            eprintln!("{} unknown configuration key {key}", "Warning".yellow());
+        }
+
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unknown(source: &str) -> Vec<String> {
+        let (_, unknown) = Config::parse(source).unwrap();
+        unknown.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn locates_unknown_keys() {
+        assert_eq!(
+            unknown("[now]\nnerd_fonts = false\n\n[now.extra]\ncolour = 1\n"),
+            ["`now.extra` (line 4)"]
+        );
+        assert_eq!(
+            unknown("[metadata]\nstorefront = \"gb\"\nstorefornt = \"us\"\n"),
+            ["`metadata.storefornt` (line 3)"]
+        );
+    }
+
+    #[test]
+    fn locates_unknown_keys_in_arrays_of_tables() {
+        let source = "[[hooks]]\ncommand = \"true\"\n\n[[hooks]]\nurl = \"http://localhost\"\nmethod = \"PUT\"\n";
+        assert_eq!(unknown(source), ["`hooks.1.method` (line 6)"]);
+    }
+
+    #[test]
+    fn reports_type_errors_with_lines() {
+        let err = Config::parse("[now]\nbar_width = 30\nlyrics = \"yes\"\n").unwrap_err();
+        assert!(err.to_string().contains("line 3"), "{err}");
+    }
+
+    #[test]
+    fn rejects_out_of_range_values() {
+        for source in [
+            "[now]\npoll_interval_ms = 0\n",
+            "[metadata]\nmin_confidence = 1.5\n",
+            "[metadata]\nmin_confidence = -0.1\n",
+            "[[hooks]]\ncommand = \"true\"\ntimeout_secs = 0\n",
+        ] {
+            assert!(Config::parse(source).is_err(), "{source}");
+        }
+
+        let (config, _) = Config::parse("[metadata]\nmin_confidence = 1\n").unwrap();
+        assert!((config.metadata.min_confidence - 1.).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,336 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncBufReadExt as _, AsyncWriteExt as _, BufReader, Lines},
+    net::{
+        UnixStream,
+        unix::{OwnedReadHalf, OwnedWriteHalf},
+    },
+    sync::Mutex,
+    time,
+};
+
+use async_trait::async_trait;
+use eyre::{Result, bail, eyre};
+use serde::de::DeserializeOwned;
+use serde_json::json;
+
+use super::{Request, Response, Status, socket_path};
+use crate::{
+    config::MetadataConfig,
+    music::{self, Metadata, PlayerBackend, PlayerState, Playlist, RepeatMode, ShuffleMode, Track},
+};
+
+/// How long to wait for the daemon to answer, which includes the time Music takes to
+/// carry out commands.
+const CALL_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug)]
+struct Connection {
+    lines: Lines<BufReader<OwnedReadHalf>>,
+    writer: OwnedWriteHalf,
+}
+
+/// A connection to a running daemon.
+#[derive(Debug)]
+pub struct Client {
+    connection: Mutex<Connection>,
+    next_id: AtomicU64,
+}
+
+impl Client {
+    /// Connect to the daemon, if it is running.
+    pub async fn connect() -> Option<Self> {
+        let stream = UnixStream::connect(socket_path().ok()?).await.ok()?;
+        let (reader, writer) = stream.into_split();
+
+        Some(Self {
+            connection: Mutex::new(Connection {
+                lines: BufReader::new(reader).lines(),
+                writer,
+            }),
+            next_id: AtomicU64::new(1),
+        })
+    }
+
+    pub async fn call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<T> {
+        let id = serde_json::Value::from(self.next_id.fetch_add(1, Ordering::Relaxed));
+
+        let mut line = serde_json::to_vec(&Request {
+            jsonrpc: "2.0".into(),
+            id: Some(id.clone()),
+            method: method.into(),
+            params,
+        })?;
+        line.push(b'\n');
+
+        let mut connection = self.connection.lock().await;
+
+        let response = time::timeout(CALL_TIMEOUT, async {
+            connection.writer.write_all(&line).await?;
+
+            loop {
+                let line = connection
+                    .lines
+                    .next_line()
+                    .await?
+                    .ok_or_else(|| eyre!("The daemon closed the connection"))?;
+
+                let response = serde_json::from_str::<Response>(&line)?;
+
+                // Skip notifications and answers to calls that timed out
+                if response.id.as_ref() == Some(&id) {
+                    return Ok::<_, eyre::Report>(response);
+                }
+            }
+        })
+        .await
+        .map_err(|_| eyre!("The daemon didn't answer `{method}` in time"))??;
+
+        if let Some(error) = response.error {
+            bail!("{} (daemon error {})", error.message, error.code);
+        }
+
+        Ok(serde_json::from_value(response.result.unwrap_or_default())?)
+    }
+
+    pub async fn status(&self) -> Result<Status> {
+        self.call("status", serde_json::Value::Null).await
+    }
+
+    /// Have the daemon poll the player right away, after changing it behind its back.
+    async fn refresh(&self) -> Result<()> {
+        self.call::<Status>("refresh", serde_json::Value::Null)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn command(&self, method: &str, params: serde_json::Value) -> Result<()> {
+        self.call::<Status>(method, params).await?;
+
+        Ok(())
+    }
+}
+
+/// Fetch the metadata for `track` through the daemon if it is running, which caches it,
+/// or directly otherwise.
+pub async fn fetch_metadata(track: &Track, config: &MetadataConfig) -> Result<Metadata> {
+    if let Some(client) = Client::connect().await {
+        return client.call("metadata", json!({ "track": track })).await;
+    }
+
+    music::fetch_metadata(track, config).await
+}
+
+/// A [`PlayerBackend`] that answers from the daemon's cached state and sends it transport
+/// commands, leaving everything the daemon doesn't handle to `fallback`.
+#[derive(Debug)]
+pub struct DaemonBackend<B> {
+    client: Client,
+    fallback: B,
+}
+
+impl<B: PlayerBackend> DaemonBackend<B> {
+    pub fn new(client: Client, fallback: B) -> Self {
+        Self { client, fallback }
+    }
+
+    /// Tell the daemon about a change made through `fallback`.
+    async fn refreshed(&self, result: Result<()>) -> Result<()> {
+        result?;
+        self.client.refresh().await
+    }
+}
+
+#[async_trait]
+impl<B: PlayerBackend> PlayerBackend for DaemonBackend<B> {
+    async fn is_running(&self) -> Result<bool> {
+        Ok(self.client.status().await?.running)
+    }
+
+    async fn player_state(&self) -> Result<PlayerState> {
+        Ok(self.client.status().await?.state)
+    }
+
+    async fn player_position(&self) -> Result<Option<f64>> {
+        Ok(self.client.status().await?.position)
+    }
+
+    async fn set_player_position(&self, position: f64) -> Result<()> {
+        self.client
+            .command("seek", json!({ "position": position }))
+            .await
+    }
+
+    async fn current_track_id(&self) -> Result<Option<String>> {
+        Ok(self.client.status().await?.track.map(|track| track.id))
+    }
+
+    async fn current_track(&self) -> Result<Option<Track>> {
+        Ok(self.client.status().await?.track)
+    }
+
+    async fn current_track_lyrics(&self) -> Result<Option<String>> {
+        self.fallback.current_track_lyrics().await
+    }
+
+    async fn current_playlist(&self) -> Result<Option<Playlist>> {
+        Ok(self.client.status().await?.playlist)
+    }
+
+    async fn set_favorited(&self, favorited: bool) -> Result<()> {
+        self.refreshed(self.fallback.set_favorited(favorited).await)
+            .await
+    }
+
+    async fn set_disliked(&self, disliked: bool) -> Result<()> {
+        self.refreshed(self.fallback.set_disliked(disliked).await)
+            .await
+    }
+
+    async fn set_rating(&self, rating: u8) -> Result<()> {
+        self.refreshed(self.fallback.set_rating(rating).await).await
+    }
+
+    async fn upcoming_tracks(&self, limit: usize) -> Result<Vec<Track>> {
+        self.fallback.upcoming_tracks(limit).await
+    }
+
+    async fn play_upcoming(&self, offset: usize) -> Result<()> {
+        self.refreshed(self.fallback.play_upcoming(offset).await)
+            .await
+    }
+
+    async fn volume(&self) -> Result<u8> {
+        match self.client.status().await?.volume {
+            Some(volume) => Ok(volume),
+            None => self.fallback.volume().await,
+        }
+    }
+
+    async fn set_volume(&self, volume: u8) -> Result<()> {
+        self.client
+            .command("volume", json!({ "volume": volume }))
+            .await
+    }
+
+    async fn shuffle_enabled(&self) -> Result<bool> {
+        match self.client.status().await?.shuffle_enabled {
+            Some(enabled) => Ok(enabled),
+            None => self.fallback.shuffle_enabled().await,
+        }
+    }
+
+    async fn set_shuffle_enabled(&self, enabled: bool) -> Result<()> {
+        self.refreshed(self.fallback.set_shuffle_enabled(enabled).await)
+            .await
+    }
+
+    async fn shuffle_mode(&self) -> Result<ShuffleMode> {
+        match self.client.status().await?.shuffle_mode {
+            Some(mode) => Ok(mode),
+            None => self.fallback.shuffle_mode().await,
+        }
+    }
+
+    async fn set_shuffle_mode(&self, mode: ShuffleMode) -> Result<()> {
+        self.refreshed(self.fallback.set_shuffle_mode(mode).await)
+            .await
+    }
+
+    async fn repeat_mode(&self) -> Result<RepeatMode> {
+        match self.client.status().await?.repeat_mode {
+            Some(mode) => Ok(mode),
+            None => self.fallback.repeat_mode().await,
+        }
+    }
+
+    async fn set_repeat_mode(&self, mode: RepeatMode) -> Result<()> {
+        self.refreshed(self.fallback.set_repeat_mode(mode).await)
+            .await
+    }
+
+    async fn search_tracks(&self, query: &str, limit: usize) -> Result<Vec<Track>> {
+        self.fallback.search_tracks(query, limit).await
+    }
+
+    async fn search_playlists(&self, query: &str) -> Result<Vec<Playlist>> {
+        self.fallback.search_playlists(query).await
+    }
+
+    async fn playlists(&self) -> Result<Vec<Playlist>> {
+        self.fallback.playlists().await
+    }
+
+    async fn playlist_tracks(&self, name: &str) -> Result<Vec<Track>> {
+        self.fallback.playlist_tracks(name).await
+    }
+
+    async fn create_playlist(&self, name: &str) -> Result<()> {
+        self.fallback.create_playlist(name).await
+    }
+
+    async fn add_to_playlist(&self, name: &str, track_id: &str) -> Result<()> {
+        self.refreshed(self.fallback.add_to_playlist(name, track_id).await)
+            .await
+    }
+
+    async fn remove_from_playlist(&self, name: &str, track_id: &str) -> Result<()> {
+        self.refreshed(self.fallback.remove_from_playlist(name, track_id).await)
+            .await
+    }
+
+    async fn play_track(&self, id: &str) -> Result<()> {
+        self.refreshed(self.fallback.play_track(id).await).await
+    }
+
+    async fn play_playlist(&self, name: &str) -> Result<()> {
+        self.refreshed(self.fallback.play_playlist(name).await)
+            .await
+    }
+
+    async fn play(&self) -> Result<()> {
+        self.client.command("play", serde_json::Value::Null).await
+    }
+
+    async fn pause(&self) -> Result<()> {
+        self.client.command("pause", serde_json::Value::Null).await
+    }
+
+    async fn back_track(&self) -> Result<()> {
+        self.refreshed(self.fallback.back_track().await).await
+    }
+
+    async fn fast_forward(&self) -> Result<()> {
+        self.refreshed(self.fallback.fast_forward().await).await
+    }
+
+    async fn next_track(&self) -> Result<()> {
+        self.client.command("next", serde_json::Value::Null).await
+    }
+
+    async fn previous_track(&self) -> Result<()> {
+        self.client
+            .command("previous", serde_json::Value::Null)
+            .await
+    }
+
+    async fn resume(&self) -> Result<()> {
+        self.refreshed(self.fallback.resume().await).await
+    }
+
+    async fn refresh(&self) -> Result<()> {
+        self.client.refresh().await
+    }
+}
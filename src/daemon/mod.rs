@@ -0,0 +1,187 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! A background process that owns the polling loop and serves the player's state over a
+//! Unix socket at `$XDG_STATE_HOME/am/daemon.sock`, so that other invocations don't have to
+//! ask Music themselves.
+//!
+//! The protocol is JSON-RPC 2.0 with one message per line. The methods are `status`,
+//! `subscribe`, `refresh`, `metadata`, `play`, `pause`, `next`, `previous`, `seek` (with a
+//! `position` in seconds) and `volume` (with a `volume` from 0 to 100). After `subscribe`,
+//! a `status` notification is sent on the same connection whenever the status changes.
+
+use std::path::PathBuf;
+
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dirs,
+    events::SEEK_TOLERANCE,
+    history::Observation,
+    music::{PlayerState, Playlist, RepeatMode, ShuffleMode, Track},
+};
+
+mod client;
+mod server;
+
+pub use client::{Client, DaemonBackend, fetch_metadata};
+pub use server::serve;
+
+pub fn socket_path() -> Result<PathBuf> {
+    Ok(dirs::state_dir()?.join("daemon.sock"))
+}
+
+/// Everything the daemon knows about the player as of its last poll.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Status {
+    pub running: bool,
+    pub state: PlayerState,
+    /// Position in the current track in seconds
+    pub position: Option<f64>,
+    pub track: Option<Track>,
+    pub playlist: Option<Playlist>,
+    pub volume: Option<u8>,
+    pub shuffle_enabled: Option<bool>,
+    pub shuffle_mode: Option<ShuffleMode>,
+    pub repeat_mode: Option<RepeatMode>,
+}
+
+impl Default for Status {
+    fn default() -> Self {
+        Self {
+            running: false,
+            state: PlayerState::Stopped,
+            position: None,
+            track: None,
+            playlist: None,
+            volume: None,
+            shuffle_enabled: None,
+            shuffle_mode: None,
+            repeat_mode: None,
+        }
+    }
+}
+
+impl Status {
+    /// What the status means for listening history.
+    fn observation(&self) -> Observation {
+        match (self.state, &self.track, self.position) {
+            (PlayerState::Playing, Some(track), Some(position)) => {
+                Observation::Playing(track.clone(), position)
+            }
+            (PlayerState::Playing | PlayerState::Stopped, _, _) => Observation::Stopped,
+            _ => Observation::Paused,
+        }
+    }
+
+    /// Whether anything changed between `self` and `other`, `elapsed` seconds later, other
+    /// than the position moving along with playback.
+    fn changed(&self, other: &Self, elapsed: f64) -> bool {
+        let seeked = match (self.position, other.position) {
+            (Some(before), Some(after)) => {
+                let expected = if self.state == PlayerState::Playing {
+                    before + elapsed
+                } else {
+                    before
+                };
+
+                (after - expected).abs() > SEEK_TOLERANCE
+            }
+            (before, after) => before.is_some() != after.is_some(),
+        };
+
+        seeked
+            || Self {
+                position: None,
+                ..self.clone()
+            } != Self {
+                position: None,
+                ..other.clone()
+            }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Request {
+    jsonrpc: String,
+    /// Missing for notifications, which get no response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<serde_json::Value>,
+    method: String,
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    params: serde_json::Value,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Response {
+    jsonrpc: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<serde_json::Value>,
+    /// Set for notifications sent by the daemon
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    method: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    params: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    result: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct RpcError {
+    code: i32,
+    message: String,
+}
+
+impl RpcError {
+    const PARSE_ERROR: i32 = -32700;
+    const METHOD_NOT_FOUND: i32 = -32601;
+    const INVALID_PARAMS: i32 = -32602;
+    /// Errors from Music itself
+    const SERVER_ERROR: i32 = -32000;
+
+    fn new(code: i32, message: impl std::fmt::Display) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl Response {
+    fn result(id: Option<serde_json::Value>, result: serde_json::Value) -> Self {
+        Self {
+            jsonrpc: "2.0".into(),
+            id,
+            method: None,
+            params: None,
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    fn error(id: Option<serde_json::Value>, error: RpcError) -> Self {
+        Self {
+            jsonrpc: "2.0".into(),
+            id,
+            method: None,
+            params: None,
+            result: None,
+            error: Some(error),
+        }
+    }
+
+    fn notification(method: &str, params: serde_json::Value) -> Self {
+        Self {
+            jsonrpc: "2.0".into(),
+            id: None,
+            method: Some(method.into()),
+            params: Some(params),
+            result: None,
+            error: None,
+        }
+    }
+}
@@ -0,0 +1,363 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+    os::unix::fs::PermissionsExt as _,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{
+    fs,
+    io::{AsyncBufReadExt as _, AsyncWriteExt as _, BufReader},
+    net::{UnixListener, UnixStream, unix::OwnedWriteHalf},
+    signal::{self, unix::SignalKind},
+    sync::watch,
+    time,
+};
+
+use anstream::{eprintln, println};
+use eyre::{Result, bail};
+use owo_colors::OwoColorize as _;
+use serde::{Deserialize, de::DeserializeOwned};
+
+use super::{Request, Response, RpcError, Status, socket_path};
+use crate::{
+    config::Config,
+    history::Recorder,
+    music::{self, PlayerBackend, PlayerState, Track},
+};
+
+/// A status along with when it was polled.
+#[derive(Debug)]
+struct Snapshot {
+    status: Status,
+    polled_at: Instant,
+}
+
+impl Snapshot {
+    /// The status with the position moved along by the time since it was polled.
+    fn current(&self) -> Status {
+        let mut status = self.status.clone();
+
+        if status.state == PlayerState::Playing
+            && let Some(position) = &mut status.position
+        {
+            *position += self.polled_at.elapsed().as_secs_f64();
+
+            if let Some(track) = &status.track {
+                *position = position.min(track.duration);
+            }
+        }
+
+        status
+    }
+}
+
+#[derive(Deserialize)]
+struct MetadataParams {
+    track: Track,
+}
+
+#[derive(Deserialize)]
+struct SeekParams {
+    position: f64,
+}
+
+#[derive(Deserialize)]
+struct VolumeParams {
+    volume: u8,
+}
+
+#[derive(Debug)]
+struct Daemon<B> {
+    backend: B,
+    config: Config,
+    status: watch::Sender<Snapshot>,
+}
+
+impl<B: PlayerBackend> Daemon<B> {
+    async fn poll(&self) -> Result<Status> {
+        let backend = &self.backend;
+
+        if !backend.is_running().await? {
+            return Ok(Status::default());
+        }
+
+        let state = backend.player_state().await?;
+        let playing = state != PlayerState::Stopped;
+
+        // A query failing, e.g. for a track that is still loading, only leaves out what it
+        // would have answered rather than the whole status
+        Ok(Status {
+            running: true,
+            state,
+            position: if playing {
+                backend.player_position().await.ok().flatten()
+            } else {
+                None
+            },
+            track: if playing {
+                backend.current_track().await.ok().flatten()
+            } else {
+                None
+            },
+            playlist: if playing {
+                backend.current_playlist().await.ok().flatten()
+            } else {
+                None
+            },
+            volume: backend.volume().await.ok(),
+            shuffle_enabled: backend.shuffle_enabled().await.ok(),
+            shuffle_mode: backend.shuffle_mode().await.ok(),
+            repeat_mode: backend.repeat_mode().await.ok(),
+        })
+    }
+
+    /// Poll the player, notifying subscribers if anything changed.
+    async fn refresh(&self) -> Result<Status> {
+        let status = self.poll().await?;
+
+        self.status.send_if_modified(|snapshot| {
+            let elapsed = snapshot.polled_at.elapsed().as_secs_f64();
+            let changed = snapshot.status.changed(&status, elapsed);
+
+            *snapshot = Snapshot {
+                status: status.clone(),
+                polled_at: Instant::now(),
+            };
+
+            changed
+        });
+
+        Ok(status)
+    }
+
+    async fn handle(&self, request: Request) -> Result<serde_json::Value, RpcError> {
+        fn params<T: DeserializeOwned>(params: serde_json::Value) -> Result<T, RpcError> {
+            serde_json::from_value(params)
+                .map_err(|err| RpcError::new(RpcError::INVALID_PARAMS, err))
+        }
+
+        let backend = &self.backend;
+
+        let status = match request.method.as_str() {
+            "status" | "subscribe" => {
+                let status = self.status.borrow().current();
+                Ok(status)
+            }
+            "refresh" => self.refresh().await,
+            "metadata" => {
+                let MetadataParams { track } = params(request.params)?;
+                let metadata = music::fetch_metadata(&track, &self.config.metadata).await?;
+                return Ok(serde_json::to_value(metadata)?);
+            }
+            "play" => self.command(backend.play()).await,
+            "pause" => self.command(backend.pause()).await,
+            "next" => self.command(backend.next_track()).await,
+            "previous" => self.command(backend.previous_track()).await,
+            "seek" => {
+                let SeekParams { position } = params(request.params)?;
+                self.command(backend.set_player_position(position)).await
+            }
+            "volume" => {
+                let VolumeParams { volume } = params(request.params)?;
+                self.command(backend.set_volume(volume.min(100))).await
+            }
+            method => {
+                return Err(RpcError::new(
+                    RpcError::METHOD_NOT_FOUND,
+                    format!("unknown method {method:?}"),
+                ));
+            }
+        }?;
+
+        Ok(serde_json::to_value(status)?)
+    }
+
+    /// Perform `action` and respond with the status after it.
+    async fn command(&self, action: impl Future<Output = Result<()>>) -> Result<Status> {
+        action.await?;
+        self.refresh().await
+    }
+}
+
+impl From<serde_json::Error> for RpcError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::new(Self::SERVER_ERROR, err)
+    }
+}
+
+impl From<eyre::Report> for RpcError {
+    fn from(err: eyre::Report) -> Self {
+        Self::new(Self::SERVER_ERROR, err)
+    }
+}
+
+async fn send(writer: &mut OwnedWriteHalf, response: &Response) -> Result<()> {
+    let mut line = serde_json::to_vec(response)?;
+    line.push(b'\n');
+    writer.write_all(&line).await?;
+
+    Ok(())
+}
+
+/// Answer requests on `stream` until it is closed, along with status notifications once it
+/// subscribes to them.
+async fn connection<B: PlayerBackend>(daemon: Arc<Daemon<B>>, stream: UnixStream) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    let mut subscription: Option<watch::Receiver<Snapshot>> = None;
+
+    loop {
+        tokio::select! {
+            line = lines.next_line() => {
+                let Some(line) = line? else {
+                    break;
+                };
+
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                let request = match serde_json::from_str::<Request>(&line) {
+                    Ok(request) => request,
+                    Err(err) => {
+                        let error = RpcError::new(RpcError::PARSE_ERROR, err);
+                        send(&mut writer, &Response::error(None, error)).await?;
+                        continue;
+                    }
+                };
+
+                if request.method == "subscribe" {
+                    subscription = Some(daemon.status.subscribe());
+                }
+
+                let id = request.id.clone();
+                let result = daemon.handle(request).await;
+
+                // Notifications don't get a response
+                if id.is_none() {
+                    continue;
+                }
+
+                let response = match result {
+                    Ok(result) => Response::result(id, result),
+                    Err(error) => Response::error(id, error),
+                };
+
+                send(&mut writer, &response).await?;
+            }
+
+            Some(status) = async {
+                let subscription = subscription.as_mut()?;
+                subscription.changed().await.ok()?;
+                Some(subscription.borrow_and_update().current())
+            } => {
+                let notification = Response::notification("status", serde_json::to_value(status)?);
+                send(&mut writer, &notification).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Listen on `path`, replacing a socket left behind by a daemon that didn't shut down
+/// cleanly.
+async fn bind(path: &Path) -> Result<UnixListener> {
+    if UnixStream::connect(path).await.is_ok() {
+        bail!("The daemon is already running");
+    }
+
+    match fs::remove_file(path).await {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err.into()),
+        _ => {}
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+
+    let listener = UnixListener::bind(path)?;
+    fs::set_permissions(path, std::fs::Permissions::from_mode(0o600)).await?;
+
+    Ok(listener)
+}
+
+/// Poll the player and serve its state on the daemon socket until interrupted, recording
+/// listening history along the way if enabled.
+pub async fn serve(backend: impl PlayerBackend + 'static, config: &Config) -> Result<()> {
+    let path = socket_path()?;
+    let listener = bind(&path).await?;
+
+    let poll_interval = Duration::from_secs(config.daemon.poll_interval_secs.get());
+    let mut history = config.history.enabled.then(|| Recorder::new(poll_interval));
+
+    let daemon = Arc::new(Daemon {
+        backend,
+        config: config.clone(),
+        status: watch::Sender::new(Snapshot {
+            status: Status::default(),
+            polled_at: Instant::now(),
+        }),
+    });
+
+    println!(
+        "{} on {}",
+        "Listening".green(),
+        path.display().to_string().cyan()
+    );
+
+    let mut intvl = time::interval(poll_interval);
+    // Service managers such as launchd stop the daemon with SIGTERM rather than SIGINT
+    let mut terminate = signal::unix::signal(SignalKind::terminate())?;
+
+    loop {
+        tokio::select! {
+            _ = intvl.tick() => {
+                let result = match (daemon.refresh().await, &mut history) {
+                    (Ok(status), Some(history)) => history.update(&status.observation()).await,
+                    (Ok(_), None) => Ok(()),
+                    (Err(err), _) => Err(err),
+                };
+
+                if let Err(err) = result {
+                    eprintln!("{} {}", "Error".red(), err);
+                }
+            }
+
+            accepted = listener.accept() => {
+                match accepted {
+                    Ok((stream, _)) => {
+                        let daemon = daemon.clone();
+
+                        tokio::spawn(async move {
+                            if let Err(err) = connection(daemon, stream).await {
+                                eprintln!("{} {}", "Error".red(), err);
+                            }
+                        });
+                    }
+                    Err(err) => eprintln!("{} {}", "Error".red(), err),
+                }
+            }
+
+            _ = signal::ctrl_c() => {
+                break;
+            }
+
+            _ = terminate.recv() => {
+                break;
+            }
+        }
+    }
+
+    println!("{} daemon", "Shutting down".yellow());
+    fs::remove_file(&path).await?;
+    if let Some(history) = &mut history {
+        history.finish().await?;
+    }
+
+    Ok(())
+}
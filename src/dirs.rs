@@ -0,0 +1,32 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::path::PathBuf;
+
+use eyre::Result;
+
+fn xdg_dir(var: &str, fallback: &[&str]) -> Result<PathBuf> {
+    if let Some(dir) = std::env::var_os(var).filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(dir).join("am"));
+    }
+
+    let mut dir = PathBuf::from(std::env::var("HOME")?);
+    dir.extend(fallback);
+    Ok(dir.join("am"))
+}
+
+/// `$XDG_CONFIG_HOME/am`, defaulting to `~/.config/am`
+pub fn config_dir() -> Result<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", &[".config"])
+}
+
+/// `$XDG_STATE_HOME/am`, defaulting to `~/.local/state/am`
+pub fn state_dir() -> Result<PathBuf> {
+    xdg_dir("XDG_STATE_HOME", &[".local", "state"])
+}
+
+/// `$XDG_CACHE_HOME/am`, defaulting to `~/.cache/am`
+pub fn cache_dir() -> Result<PathBuf> {
+    xdg_dir("XDG_CACHE_HOME", &[".cache"])
+}
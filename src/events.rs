@@ -0,0 +1,380 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Playback events, detected by comparing the state of the player between polls, and the
+//! hooks that run when they happen.
+
+use std::{
+    path::PathBuf,
+    process::Stdio,
+    time::{Duration, Instant},
+};
+use tokio::{fs, io::AsyncWriteExt as _, process::Command, time};
+
+use anstream::eprintln;
+use chrono::{DateTime, Local};
+use clap::ValueEnum;
+use eyre::{Result, bail};
+use owo_colors::OwoColorize as _;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::HookConfig,
+    dirs, http,
+    music::{PlayerState, Playlist, Track},
+};
+
+/// Seconds the position may drift from where it is expected to be before it counts as a
+/// seek, allowing for the time it takes to query it.
+pub const SEEK_TOLERANCE: f64 = 2.;
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// A different track started playing
+    TrackChanged { track: Track },
+    /// Playback started, paused or stopped
+    StateChanged {
+        state: PlayerState,
+        track: Option<Track>,
+    },
+    /// The position in the current track jumped, rather than moving along with playback
+    Seeked { position: f64, track: Option<Track> },
+    /// Playback moved to a different playlist
+    PlaylistChanged { playlist: Option<Playlist> },
+    /// Music was quit
+    Quit,
+    /// Everything known about the player when `am events` starts, before any changes
+    Snapshot {
+        running: bool,
+        state: PlayerState,
+        position: Option<f64>,
+        track: Option<Track>,
+        playlist: Option<Playlist>,
+    },
+}
+
+#[derive(Serialize, Deserialize, ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[value(rename_all = "snake_case")]
+pub enum EventKind {
+    TrackChanged,
+    StateChanged,
+    Seeked,
+    PlaylistChanged,
+    Quit,
+    /// Always printed first by `am events`, so it can't be filtered
+    #[value(skip)]
+    Snapshot,
+}
+
+impl EventKind {
+    /// Whether hooks run for the event, rather than it only being reported by `am events`.
+    pub fn is_hookable(self) -> bool {
+        matches!(self, Self::TrackChanged | Self::StateChanged | Self::Quit)
+    }
+}
+
+impl Event {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Self::TrackChanged { .. } => EventKind::TrackChanged,
+            Self::StateChanged { .. } => EventKind::StateChanged,
+            Self::Seeked { .. } => EventKind::Seeked,
+            Self::PlaylistChanged { .. } => EventKind::PlaylistChanged,
+            Self::Quit => EventKind::Quit,
+            Self::Snapshot { .. } => EventKind::Snapshot,
+        }
+    }
+}
+
+/// An event as sent to hooks.
+#[derive(Serialize, Debug, Clone)]
+pub struct Payload {
+    #[serde(flatten)]
+    pub event: Event,
+    pub timestamp: DateTime<Local>,
+}
+
+impl Payload {
+    pub fn new(event: Event) -> Self {
+        Self {
+            event,
+            timestamp: Local::now(),
+        }
+    }
+
+    /// The fields of the payload as `AM_`-prefixed environment variables, with nested
+    /// fields joined by underscores, e.g. `AM_EVENT` and `AM_TRACK_NAME`.
+    fn env(&self) -> Result<Vec<(String, String)>> {
+        fn flatten(prefix: &str, value: &serde_json::Value, vars: &mut Vec<(String, String)>) {
+            match value {
+                serde_json::Value::Object(object) => {
+                    for (key, value) in object {
+                        flatten(&format!("{prefix}_{}", key.to_uppercase()), value, vars);
+                    }
+                }
+                serde_json::Value::String(string) => vars.push((prefix.to_owned(), string.clone())),
+                serde_json::Value::Null | serde_json::Value::Array(_) => {}
+                value => vars.push((prefix.to_owned(), value.to_string())),
+            }
+        }
+
+        let mut vars = Vec::new();
+        flatten("AM", &serde_json::to_value(self)?, &mut vars);
+
+        Ok(vars)
+    }
+}
+
+/// Detects changes between successive observations of the player. The first observation of
+/// each kind is never a change.
+#[derive(Debug, Clone, Default)]
+pub struct Detector {
+    running: Option<bool>,
+    state: Option<PlayerState>,
+    track_id: Option<String>,
+    /// Whether the track has been observed yet
+    seen_track: bool,
+    playlist_name: Option<String>,
+    /// Whether the playlist has been observed yet
+    seen_playlist: bool,
+    /// The last position observed in the current track, with the state at the time
+    position: Option<(f64, PlayerState, Instant)>,
+}
+
+impl Detector {
+    /// Observe whether Music is running, returning whether it was just quit.
+    pub fn running(&mut self, running: bool) -> bool {
+        let quit = self.running.replace(running) == Some(true) && !running;
+
+        if !running {
+            // Starting Music again and playing something are changes
+            self.state = Some(PlayerState::Stopped);
+            self.track_id = None;
+            self.seen_track = true;
+            self.playlist_name = None;
+            self.seen_playlist = true;
+            self.position = None;
+        }
+
+        quit
+    }
+
+    /// Observe the player state, returning whether it changed.
+    pub fn state(&mut self, state: PlayerState) -> bool {
+        self.state
+            .replace(state)
+            .is_some_and(|previous| previous != state)
+    }
+
+    /// Observe the current track, returning whether a different one is playing now.
+    pub fn track(&mut self, track: Option<&Track>) -> bool {
+        let id = track.map(|track| track.id.clone());
+        let changed = self.seen_track && self.track_id != id;
+
+        if self.track_id != id {
+            self.position = None;
+        }
+
+        self.track_id = id;
+        self.seen_track = true;
+        changed && track.is_some()
+    }
+
+    /// Observe the current playlist, returning whether a different one is playing now.
+    pub fn playlist(&mut self, playlist: Option<&Playlist>) -> bool {
+        let name = playlist.map(|playlist| playlist.name.clone());
+        let changed = self.seen_playlist && self.playlist_name != name;
+
+        self.playlist_name = name;
+        self.seen_playlist = true;
+        changed
+    }
+
+    /// Observe the position in the current track in the given state, returning whether it
+    /// jumped since the last observation. Call this after [`track`](Self::track), so that
+    /// moving to another track doesn't count.
+    pub fn position(&mut self, position: Option<f64>, state: PlayerState) -> bool {
+        let seeked = match (self.position, position) {
+            // Changing state in between leaves no telling how far playback got
+            (Some((previous, previous_state, at)), Some(position)) if previous_state == state => {
+                let expected = if state == PlayerState::Playing {
+                    previous + at.elapsed().as_secs_f64()
+                } else {
+                    previous
+                };
+
+                (position - expected).abs() > SEEK_TOLERANCE
+            }
+            _ => false,
+        };
+
+        self.position = position.map(|position| (position, state, Instant::now()));
+        seeked
+    }
+}
+
+/// The hooks declared in the configuration file.
+#[derive(Debug, Clone, Default)]
+pub struct Hooks {
+    hooks: Vec<HookConfig>,
+    /// Where to append failures instead of printing them
+    log: Option<PathBuf>,
+}
+
+impl Hooks {
+    pub fn new(hooks: &[HookConfig]) -> Result<Self> {
+        for (index, hook) in hooks.iter().enumerate() {
+            if hook.command.is_some() == hook.url.is_some() {
+                bail!("Hook {} needs either a `command` or a `url`", index + 1);
+            }
+        }
+
+        Ok(Self {
+            hooks: hooks.to_vec(),
+            log: None,
+        })
+    }
+
+    /// Append failures to `$XDG_STATE_HOME/am/hooks.log` instead of printing them, for when
+    /// a full-screen UI owns the terminal.
+    pub fn log_to_file(self) -> Result<Self> {
+        Ok(Self {
+            log: Some(dirs::state_dir()?.join("hooks.log")),
+            ..self
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Run the hooks for `event` in the background, logging any that fail.
+    pub fn dispatch(&self, event: Event) {
+        let payload = Payload::new(event);
+
+        for (index, hook) in self.hooks.iter().enumerate() {
+            if !hook.events.is_empty() && !hook.events.contains(&payload.event.kind()) {
+                continue;
+            }
+
+            let hook = hook.clone();
+            let payload = payload.clone();
+            let log = self.log.clone();
+
+            tokio::spawn(async move {
+                let timeout = Duration::from_secs(hook.timeout_secs.get());
+                let result = match time::timeout(timeout, run(&hook, &payload)).await {
+                    Ok(result) => result,
+                    Err(_) => Err(eyre::eyre!("timed out after {timeout:?}")),
+                };
+
+                if let Err(err) = result {
+                    let message = format!("hook {} failed: {err}", index + 1);
+
+                    if let Some(log) = log {
+                        // There's nowhere left to report it if even this fails
+                        let _ = append_log(log, &message).await;
+                    } else {
+                        eprintln!("{} {message}", "Error".red());
+                    }
+                }
+            });
+        }
+    }
+}
+
+async fn append_log(path: PathBuf, message: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+
+    let line = format!("{} {message}\n", Local::now().to_rfc3339());
+
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?
+        .write_all(line.as_bytes())
+        .await?;
+
+    Ok(())
+}
+
+async fn run(hook: &HookConfig, payload: &Payload) -> Result<()> {
+    if let Some(command) = &hook.command {
+        // Hooks mustn't write over `am`'s own output, but what they print to stderr is
+        // kept to explain why they failed
+        let output = Command::new("sh")
+            .args(["-c", command])
+            .envs(payload.env()?)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            // Stop the command if it times out
+            .kill_on_drop(true)
+            .output()
+            .await?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let stderr = stderr.trim();
+
+            if stderr.is_empty() {
+                bail!("`{command}` exited with {}", output.status);
+            }
+
+            bail!("`{command}` exited with {}: {stderr}", output.status);
+        }
+    }
+
+    if let Some(url) = &hook.url {
+        let url = url.parse::<Url>()?;
+
+        http::webhook_client(&url)
+            .post(url)
+            .json(payload)
+            .send()
+            .await?
+            .error_for_status()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(command: &str) -> HookConfig {
+        HookConfig {
+            command: Some(command.to_owned()),
+            ..HookConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn runs_commands_with_the_event() {
+        let payload = Payload::new(Event::Quit);
+
+        run(&command(r#"test "$AM_EVENT" = quit"#), &payload)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn reports_what_failed_commands_print() {
+        let payload = Payload::new(Event::Quit);
+
+        let err = run(&command("echo out; echo oops >&2; exit 3"), &payload)
+            .await
+            .unwrap_err()
+            .to_string();
+
+        assert!(err.ends_with("exit status: 3: oops"), "{err}");
+    }
+}
@@ -71,3 +71,110 @@ pub fn format_duration_plain(duration_secs: i32) -> String {
     str = format!("{}{:.0}{}", str, duration_secs, "s");
     str
 }
+
+/// Parse a duration in seconds from `h`/`m`/`s` notation as printed by [`format_duration`]
+/// (e.g. `1h2m3s`, `1m23s`, `45s`), colon notation (e.g. `1:02:03`, `1:23`), or a plain
+/// number of seconds.
+pub fn parse_duration(s: &str) -> Option<f64> {
+    let s = s.trim();
+
+    if s.is_empty() {
+        return None;
+    }
+
+    if let Ok(secs) = s.parse::<f64>() {
+        return Some(secs).filter(|secs| secs.is_finite() && *secs >= 0.);
+    }
+
+    if s.contains(':') {
+        let parts = s.split(':').collect::<Vec<_>>();
+
+        if parts.len() > 3 {
+            return None;
+        }
+
+        let mut secs = 0.;
+
+        for (i, part) in parts.iter().enumerate() {
+            let value = if i + 1 == parts.len() {
+                part.parse::<f64>()
+                    .ok()
+                    .filter(|v| v.is_finite() && *v >= 0.)?
+            } else {
+                f64::from(part.parse::<u32>().ok()?)
+            };
+
+            secs = secs * f64::from(MINUTE) + value;
+        }
+
+        return Some(secs);
+    }
+
+    let mut secs = 0.;
+    let mut number = String::new();
+    let mut last_unit = i32::MAX;
+
+    for c in s.chars() {
+        let unit = match c {
+            '0'..='9' | '.' => {
+                number.push(c);
+                continue;
+            }
+            'h' => HOUR,
+            'm' => MINUTE,
+            's' => 1,
+            _ => return None,
+        };
+
+        // Units must be given from largest to smallest, each at most once
+        if unit >= last_unit {
+            return None;
+        }
+
+        secs += number.parse::<f64>().ok()? * f64::from(unit);
+        number.clear();
+        last_unit = unit;
+    }
+
+    number.is_empty().then_some(secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        let cases = [
+            ("1:23", Some(83.)),
+            ("1:02:03", Some(3723.)),
+            ("0:07.5", Some(7.5)),
+            ("1h2m3s", Some(3723.)),
+            ("1m23s", Some(83.)),
+            ("90s", Some(90.)),
+            ("2m", Some(120.)),
+            ("83", Some(83.)),
+            (" 12.5 ", Some(12.5)),
+            ("", None),
+            ("abc", None),
+            ("1:2:3:4", None),
+            ("1:-2", None),
+            ("3s2m", None),
+            ("1m1m", None),
+            ("1m2", None),
+            ("-5", None),
+            ("inf", None),
+        ];
+
+        for (s, expected) in cases {
+            assert_eq!(parse_duration(s), expected, "{s:?}");
+        }
+    }
+
+    #[test]
+    fn formats_plain_durations() {
+        assert_eq!(format_duration_plain(45), "45s");
+        assert_eq!(format_duration_plain(354), "5m54s");
+        assert_eq!(format_duration_plain(3723), "1h2m3s");
+    }
+}
@@ -0,0 +1,330 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Listening history, appended to `$XDG_STATE_HOME/am/history.jsonl` as tracks change.
+
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+use tokio::{fs, io::AsyncWriteExt as _};
+
+use chrono::{DateTime, Local, TimeDelta};
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dirs,
+    music::{PlayerBackend, PlayerState, Track},
+};
+
+/// Extra seconds of slack, on top of the poll interval, for deciding whether a track was
+/// played to the end.
+const END_SLACK: f64 = 5.;
+
+/// A track that was played, as recorded in the history.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Play {
+    /// Database ID of the track
+    pub track_id: String,
+    pub name: String,
+    pub album: String,
+    pub artist: String,
+    /// Duration of the track in seconds
+    pub duration: f64,
+    pub started_at: DateTime<Local>,
+    pub ended_at: DateTime<Local>,
+    /// How many seconds of the track were listened to
+    pub played: f64,
+    /// Whether the track was left before it finished
+    pub skipped: bool,
+}
+
+fn path() -> Result<PathBuf> {
+    Ok(dirs::state_dir()?.join("history.jsonl"))
+}
+
+async fn append(play: &Play) -> Result<()> {
+    let path = path()?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+
+    let mut line = serde_json::to_vec(play)?;
+    line.push(b'\n');
+
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?
+        .write_all(&line)
+        .await?;
+
+    Ok(())
+}
+
+/// Read the plays that started after `since`, or all of them, oldest first.
+pub async fn read(since: Option<DateTime<Local>>) -> Result<Vec<Play>> {
+    let data = match fs::read_to_string(path()?).await {
+        Ok(data) => data,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+
+    // A line may have been cut off if `am` was killed while writing it
+    Ok(data
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Play>(line).ok())
+        .filter(|play| since.is_none_or(|since| play.started_at >= since))
+        .collect())
+}
+
+/// What the player is doing, as far as listening is concerned.
+#[derive(Debug)]
+pub enum Observation {
+    Playing(Track, f64),
+    /// Paused tracks are still being listened to, they just don't make progress
+    Paused,
+    /// Music isn't running or has stopped playing
+    Stopped,
+}
+
+impl Observation {
+    pub async fn poll(backend: &impl PlayerBackend) -> Result<Self> {
+        if !backend.is_running().await? {
+            return Ok(Self::Stopped);
+        }
+
+        match backend.player_state().await? {
+            PlayerState::Playing => {}
+            PlayerState::Stopped => return Ok(Self::Stopped),
+            _ => return Ok(Self::Paused),
+        }
+
+        match (
+            backend.current_track().await?,
+            backend.player_position().await?,
+        ) {
+            (Some(track), Some(position)) => Ok(Self::Playing(track, position)),
+            _ => Ok(Self::Stopped),
+        }
+    }
+}
+
+/// How close to its end a track has to be left to count as played to the end, given how
+/// often the player is polled.
+pub fn end_margin(poll_interval: Duration) -> f64 {
+    poll_interval.as_secs_f64() + END_SLACK
+}
+
+/// A single listen to a track, followed from poll to poll.
+#[derive(Debug)]
+pub struct Listen {
+    pub track: Track,
+    pub started_at: DateTime<Local>,
+    /// How many seconds of the track were listened to so far
+    pub played: f64,
+    last_position: f64,
+    last_seen: Instant,
+}
+
+impl Listen {
+    #[expect(clippy::cast_possible_truncation)]
+    pub fn new(track: &Track, position: f64) -> Self {
+        Self {
+            track: track.clone(),
+            started_at: Local::now() - TimeDelta::milliseconds((position * 1000.) as i64),
+            played: 0.,
+            last_position: position,
+            last_seen: Instant::now(),
+        }
+    }
+
+    /// Whether the track was left within `end_margin` seconds of its end.
+    pub fn finished(&self, end_margin: f64) -> bool {
+        self.last_position >= self.track.duration - end_margin
+    }
+
+    /// Follow this listen to `track` at `position`, returning `false` if it is a different
+    /// track or the track started over after finishing, which makes it a new listen.
+    pub fn update(&mut self, track: &Track, position: f64, end_margin: f64) -> bool {
+        if self.track.id != track.id {
+            return false;
+        }
+
+        let delta = position - self.last_position;
+        let elapsed = self.last_seen.elapsed().as_secs_f64();
+
+        // Going back from the end is the track repeating, anything else is a seek
+        if delta < 0. && self.finished(end_margin) {
+            return false;
+        }
+
+        // Only count progress that could have been made by listening, not seeking
+        if (0. ..=elapsed + 1.).contains(&delta) {
+            self.played += delta;
+        }
+
+        self.last_position = position;
+        self.last_seen = Instant::now();
+
+        true
+    }
+}
+
+/// Follows what is playing from poll to poll and appends a [`Play`] to the history whenever
+/// the track changes.
+#[derive(Debug)]
+pub struct Recorder {
+    current: Option<Listen>,
+    /// A track counts as skipped if it was left more than this many seconds before its end
+    end_margin: f64,
+}
+
+impl Recorder {
+    pub fn new(poll_interval: Duration) -> Self {
+        Self {
+            current: None,
+            end_margin: end_margin(poll_interval),
+        }
+    }
+
+    /// Note that `track` is playing at `position`, recording the previous track if it changed.
+    pub async fn observe(&mut self, track: &Track, position: f64) -> Result<()> {
+        match self.follow(track, position) {
+            Some(play) => append(&play).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Record the current track as having ended, e.g. because Music was quit.
+    pub async fn finish(&mut self) -> Result<()> {
+        match self.take() {
+            Some(play) => append(&play).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Forget the current track without recording it, because something else is recording
+    /// the history now.
+    pub fn abandon(&mut self) {
+        self.current = None;
+    }
+
+    /// Follow `track` playing at `position`, returning the play of the previous track if it
+    /// changed.
+    fn follow(&mut self, track: &Track, position: f64) -> Option<Play> {
+        if let Some(current) = &mut self.current
+            && current.update(track, position, self.end_margin)
+        {
+            return None;
+        }
+
+        let play = self.take();
+        self.current = Some(Listen::new(track, position));
+
+        play
+    }
+
+    /// End the current listen, returning it as a play.
+    fn take(&mut self) -> Option<Play> {
+        let current = self.current.take()?;
+        let skipped = !current.finished(self.end_margin);
+
+        Some(Play {
+            track_id: current.track.id,
+            name: current.track.name,
+            album: current.track.album,
+            artist: current.track.artist,
+            duration: current.track.duration,
+            started_at: current.started_at,
+            ended_at: Local::now(),
+            played: current.played,
+            skipped,
+        })
+    }
+
+    /// Record what a poll found to be playing.
+    pub async fn update(&mut self, observation: &Observation) -> Result<()> {
+        match observation {
+            Observation::Playing(track, position) => self.observe(track, *position).await,
+            Observation::Paused => Ok(()),
+            Observation::Stopped => self.finish().await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music::fake::track;
+
+    const MARGIN: f64 = 10.;
+
+    #[test]
+    fn end_margin_follows_the_poll_interval() {
+        assert!((end_margin(Duration::from_secs(5)) - MARGIN).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn counts_listening_but_not_seeking() {
+        let song = track("1", "One");
+        let mut listen = Listen::new(&song, 10.);
+
+        assert!(listen.update(&song, 10.5, MARGIN));
+        assert!((listen.played - 0.5).abs() < 1e-9);
+
+        // Seeking forwards or backwards is still the same listen
+        assert!(listen.update(&song, 100., MARGIN));
+        assert!(listen.update(&song, 50., MARGIN));
+        assert!((listen.played - 0.5).abs() < 1e-9);
+        assert!(!listen.finished(MARGIN));
+
+        assert!(!listen.update(&track("2", "Two"), 0., MARGIN));
+    }
+
+    #[test]
+    fn starts_over_when_a_finished_track_repeats() {
+        let song = track("1", "One");
+        let mut listen = Listen::new(&song, 175.);
+
+        assert!(listen.finished(MARGIN));
+        assert!(!listen.update(&song, 1., MARGIN));
+    }
+
+    #[test]
+    fn records_a_play_when_the_track_changes() {
+        let mut recorder = Recorder::new(Duration::from_secs(5));
+
+        assert!(recorder.follow(&track("1", "One"), 0.).is_none());
+        assert!(recorder.follow(&track("1", "One"), 0.5).is_none());
+
+        let play = recorder.follow(&track("2", "Two"), 0.).unwrap();
+        assert_eq!(play.track_id, "1");
+        assert!((play.played - 0.5).abs() < 1e-9);
+        assert!(play.skipped);
+
+        // Left close enough to the end to count as played
+        assert!(recorder.follow(&track("2", "Two"), 175.).is_none());
+        let play = recorder.take().unwrap();
+        assert_eq!(play.track_id, "2");
+        assert!(!play.skipped);
+
+        assert!(recorder.take().is_none());
+    }
+
+    #[test]
+    fn records_repeats_as_separate_plays() {
+        let mut recorder = Recorder::new(Duration::from_secs(5));
+
+        recorder.follow(&track("1", "One"), 175.);
+        let play = recorder.follow(&track("1", "One"), 0.).unwrap();
+        assert!(!play.skipped);
+
+        recorder.abandon();
+        assert!(recorder.take().is_none());
+    }
+}
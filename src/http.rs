@@ -2,9 +2,43 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use std::sync::LazyLock;
+use std::{net::IpAddr, sync::LazyLock};
 
-use reqwest::Client;
+use reqwest::{Client, Url, redirect::Policy};
 
 pub static HTTP: LazyLock<Client> =
     LazyLock::new(|| Client::builder().https_only(true).build().unwrap());
+
+/// Allows plain HTTP, but doesn't follow redirects that could lead off the machine or network
+/// that the URL was configured for.
+static PLAIN_HTTP: LazyLock<Client> =
+    LazyLock::new(|| Client::builder().redirect(Policy::none()).build().unwrap());
+
+/// The client to use for a configurable API `url`, which may be a local server (e.g. a stub
+/// for testing) that only speaks plain HTTP.
+pub fn client_for(url: &Url) -> &'static Client {
+    let local = url.host_str().is_some_and(|host| {
+        host == "localhost"
+            || host
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .parse::<IpAddr>()
+                .is_ok_and(|ip| ip.is_loopback())
+    });
+
+    if local && url.scheme() == "http" {
+        &PLAIN_HTTP
+    } else {
+        &HTTP
+    }
+}
+
+/// The client to use for a webhook `url` from the config, which is often a plain HTTP server
+/// on the local network (e.g. Home Assistant).
+pub fn webhook_client(url: &Url) -> &'static Client {
+    if url.scheme() == "http" {
+        &PLAIN_HTTP
+    } else {
+        &HTTP
+    }
+}
@@ -0,0 +1,152 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Finding lyrics for tracks and parsing timed LRC lyrics.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+use eyre::Result;
+use regex::Regex;
+
+use crate::{
+    config::LyricsConfig,
+    format,
+    music::{PlayerBackend, Track},
+};
+
+/// Word timings from the enhanced LRC format, which are not used.
+static WORD_TIME_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<\d+:\d+(?:[.:]\d+)?>").unwrap());
+
+/// A line of timed lyrics.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimedLine {
+    /// When the line starts, in seconds
+    pub time: f64,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lyrics {
+    /// Lyrics without timing information
+    Plain(Vec<String>),
+    /// Lyrics with the time each line starts at, in order
+    Timed(Vec<TimedLine>),
+}
+
+impl Lyrics {
+    /// Parse lyrics in LRC format, falling back to plain lyrics if there are no timed lines.
+    pub fn parse(text: &str) -> Self {
+        let mut offset = 0.;
+        let mut lines = Vec::new();
+
+        for line in text.lines() {
+            let mut rest = line.trim();
+            let mut times = Vec::new();
+
+            while let Some(tag) = rest.strip_prefix('[') {
+                let Some((tag, after)) = tag.split_once(']') else {
+                    break;
+                };
+
+                if let Some(time) = format::parse_duration(tag) {
+                    times.push(time);
+                } else if let Some(value) = tag.strip_prefix("offset:") {
+                    // Milliseconds by which the lyrics should appear earlier
+                    offset = value.trim().parse::<f64>().unwrap_or_default() / 1000.;
+                }
+
+                rest = after;
+            }
+
+            let text = WORD_TIME_REGEX.replace_all(rest, "").trim().to_owned();
+
+            lines.extend(times.into_iter().map(|time| TimedLine {
+                time,
+                text: text.clone(),
+            }));
+        }
+
+        if lines.is_empty() {
+            return Self::Plain(text.lines().map(|line| line.trim().to_owned()).collect());
+        }
+
+        for line in &mut lines {
+            line.time = (line.time - offset).max(0.);
+        }
+        lines.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        Self::Timed(lines)
+    }
+
+    /// The text of every line.
+    pub fn lines(&self) -> Vec<&str> {
+        match self {
+            Self::Plain(lines) => lines.iter().map(String::as_str).collect(),
+            Self::Timed(lines) => lines.iter().map(|line| line.text.as_str()).collect(),
+        }
+    }
+
+    /// The index of the line being sung at `position`, for timed lyrics.
+    pub fn current_line(&self, position: f64) -> Option<usize> {
+        match self {
+            Self::Plain(_) => None,
+            Self::Timed(lines) => lines.iter().rposition(|line| line.time <= position),
+        }
+    }
+}
+
+/// Replace characters that can't appear in file names.
+fn file_name(name: &str) -> String {
+    name.replace(['/', ':'], "_")
+}
+
+/// Where LRC files for `track` might be, in order of preference.
+fn lrc_paths(track: &Track, config: &LyricsConfig) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+
+    if let Some(location) = &track.location {
+        paths.push(location.with_extension("lrc"));
+    }
+
+    if let Some(dir) = &config.dir {
+        paths.push(dir.join(format!(
+            "{} - {}.lrc",
+            file_name(&track.artist),
+            file_name(&track.name)
+        )));
+        paths.push(dir.join(format!("{}.lrc", file_name(&track.name))));
+    }
+
+    paths
+}
+
+async fn read(path: &Path) -> Option<String> {
+    tokio::fs::read_to_string(path)
+        .await
+        .ok()
+        .filter(|lyrics| !lyrics.trim().is_empty())
+}
+
+/// Find lyrics for the current track `track`, preferring LRC files over the lyrics stored
+/// with the track, which are rarely timed.
+pub async fn find(
+    backend: &impl PlayerBackend,
+    track: &Track,
+    config: &LyricsConfig,
+) -> Result<Option<Lyrics>> {
+    for path in lrc_paths(track, config) {
+        if let Some(lyrics) = read(&path).await {
+            return Ok(Some(Lyrics::parse(&lyrics)));
+        }
+    }
+
+    Ok(backend
+        .current_track_lyrics()
+        .await?
+        .map(|lyrics| Lyrics::parse(&lyrics)))
+}
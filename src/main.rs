@@ -3,7 +3,7 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use anstream::println;
-use std::io::stdout;
+use std::{io::stdout, path::PathBuf};
 
 use eyre::{Result, eyre};
 use owo_colors::OwoColorize as _;
@@ -11,11 +11,25 @@ use owo_colors::OwoColorize as _;
 use clap::{CommandFactory, Parser, Subcommand};
 use clap_complete::{Shell, generate};
 
+mod artwork;
 mod cmd;
+mod config;
+mod daemon;
+mod dirs;
+mod events;
 mod format;
+mod history;
 mod http;
+mod lyrics;
 mod music;
 mod rich_presence;
+mod scrobble;
+mod state;
+mod template;
+
+use config::Config;
+use music::{PlayerBackend, PlayerState};
+use template::Template;
 
 /// Beautiful and feature-packed Apple Music CLI
 #[derive(Parser, Debug)]
@@ -30,8 +44,47 @@ enum Commands {
     /// Show now playing
     Now(cmd::NowOptions),
 
-    /// Play the current track
-    Play,
+    /// Print a line of JSON whenever playback changes, starting with the current state
+    Events {
+        /// Only print these kinds of events, separated by commas
+        #[arg(long, value_enum, value_delimiter = ',')]
+        filter: Vec<events::EventKind>,
+    },
+
+    /// Play the current track, or search the library and play a track or playlist
+    Play {
+        /// Track or playlist to search for
+        query: Vec<String>,
+
+        /// Play the top result instead of choosing one
+        #[arg(long, requires = "query")]
+        first: bool,
+    },
+
+    /// Search the library for tracks, albums, artists and playlists
+    Search {
+        /// Text to search for
+        #[arg(required = true)]
+        query: Vec<String>,
+
+        /// Maximum number of results to show
+        #[arg(short = 'n', long, default_value_t = 10)]
+        limit: usize,
+    },
+
+    /// List, play and edit playlists
+    Playlist {
+        #[command(subcommand)]
+        command: PlaylistCommands,
+    },
+
+    /// Show the tracks up next in the current playlist
+    Queue {
+        /// Number of tracks to show
+        #[arg(short = 'n', long, default_value_t = 10)]
+        limit: usize,
+    },
+
     /// Pause playback
     Pause,
 
@@ -48,6 +101,13 @@ enum Commands {
     /// Skip forward in the current track
     Forward,
 
+    /// Seek to a position in the current track
+    Seek {
+        /// Position (1:23, 1m23s, 83), offset from the current position (+15s, -30) or percentage (50%)
+        #[arg(allow_hyphen_values = true)]
+        target: cmd::SeekTarget,
+    },
+
     /// Advance to the next track in the current playlist
     Next,
 
@@ -55,15 +115,103 @@ enum Commands {
     #[command(visible_aliases = ["prev"])]
     Previous,
 
+    /// Show or change shuffle
+    Shuffle {
+        #[arg(value_enum)]
+        action: Option<cmd::ShuffleAction>,
+    },
+
+    /// Show or change the repeat mode
+    Repeat {
+        #[arg(value_enum)]
+        action: Option<cmd::RepeatAction>,
+    },
+
+    /// Show or change the volume
+    Volume {
+        /// New volume (0-100), a relative change (+5, -5), "mute" or "unmute"
+        #[arg(allow_hyphen_values = true)]
+        change: Option<cmd::VolumeChange>,
+    },
+
+    /// Add the current track to favorites
+    #[command(visible_aliases = ["favorite"])]
+    Love,
+
+    /// Remove the current track from favorites
+    #[command(visible_aliases = ["unfavorite"])]
+    Unlove,
+
+    /// Mark the current track as disliked
+    Dislike,
+
+    /// Rate the current track
+    Rate {
+        /// Number of stars (0-5), where 0 clears the rating
+        #[arg(value_parser = clap::value_parser!(u8).range(0..=5))]
+        rating: u8,
+    },
+
+    /// Show the lyrics of the current track
+    Lyrics,
+
     /// Show the Song.link for the current track
     SongLink,
 
+    /// Show recently played tracks
+    History {
+        /// Only show tracks played within this long ago, e.g. `12h`, `7d` or `2w`
+        #[arg(long, value_parser = cmd::parse_age)]
+        since: Option<chrono::TimeDelta>,
+
+        /// Maximum number of tracks to show
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+
+        /// Output JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show statistics about the listening history
+    Stats {
+        /// Time period to include
+        #[arg(long, value_enum, default_value_t)]
+        period: cmd::Period,
+
+        /// Output JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Run in the background, serving playback state to other commands and recording
+    /// listening history
+    Daemon,
+
+    /// Scrobble to Last.fm and ListenBrainz
+    Scrobble {
+        #[command(subcommand)]
+        command: Option<ScrobbleCommands>,
+    },
+
     /// Connect to Discord rich presence
     Discord {
         #[command(subcommand)]
         command: Option<DiscordCommands>,
     },
 
+    /// Show or clear the cached track metadata
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommands,
+    },
+
+    /// Manage the configuration file
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+
     /// Generate shell completions
     Completions {
         /// Shell
@@ -72,6 +220,12 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand, Debug)]
+enum ScrobbleCommands {
+    /// Log in to Last.fm
+    Auth,
+}
+
 #[derive(Subcommand, Debug)]
 enum DiscordCommands {
     /// Install Discord presence launch agent
@@ -80,132 +234,262 @@ enum DiscordCommands {
     Uninstall,
 }
 
-#[cfg(not(target_os = "macos"))]
+#[derive(Subcommand, Debug)]
+enum PlaylistCommands {
+    /// List playlists with their track counts and durations
+    List,
+    /// Show the tracks in a playlist
+    Show {
+        /// Playlist name
+        name: String,
+    },
+    /// Play a playlist
+    Play {
+        /// Playlist name
+        name: String,
+
+        /// Enable shuffle before playing
+        #[arg(long)]
+        shuffle: bool,
+    },
+    /// Create an empty playlist
+    Create {
+        /// Playlist name
+        name: String,
+    },
+    /// Add the current track to a playlist
+    Add {
+        /// Playlist name
+        name: String,
+    },
+    /// Remove the current track from a playlist
+    Remove {
+        /// Playlist name
+        name: String,
+    },
+    /// Export the tracks in a playlist
+    Export {
+        /// Playlist name
+        name: String,
+
+        #[arg(short, long, value_enum, default_value_t)]
+        format: cmd::playlist::ExportFormat,
+
+        /// Write to a file instead of standard output
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum CacheCommands {
+    /// Show how many tracks have cached metadata
+    Stats,
+    /// Delete the cached metadata
+    Clear,
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommands {
+    /// Show the effective configuration
+    Show,
+    /// Show the path to the configuration file
+    Path,
+    /// Check the configuration file for unknown keys and invalid values
+    Validate,
+}
+
+#[cfg(all(not(target_os = "macos"), not(test)))]
 compile_error!("am doesn't work on non-macOS platforms!");
 
-#[expect(clippy::cast_possible_truncation)]
-async fn concise_now_playing() -> Result<()> {
-    let track_data = music::tell_raw(&[
-        r#"set output to """#,
-        r#"tell application "Music""#,
-        r"set t_name to name of current track",
-        r"set t_album to album of current track",
-        r"set t_artist to artist of current track",
-        r"set t_duration to duration of current track",
-        r#"set output to "" & t_name & "\n" & t_album & "\n" & t_artist & "\n" & t_duration"#,
-        r"end tell",
-        r"return output",
-    ])
-    .await?;
-
-    let mut track_data = track_data.split('\n');
-
-    let name = track_data
-        .next()
-        .ok_or_else(|| eyre!("Could not obtain track name"))?
-        .to_owned();
-    let album = track_data
-        .next()
-        .ok_or_else(|| eyre!("Could not obtain track album"))?
-        .to_owned();
-    let artist = track_data
-        .next()
-        .ok_or_else(|| eyre!("Could not obtain track artist"))?
-        .to_owned();
-    let duration = track_data
-        .next()
-        .ok_or_else(|| eyre!("Could not obtain track duration"))?
-        .to_owned()
-        .replace(',', ".")
-        .parse::<f64>()?;
+const CONCISE_TEMPLATE: &str = "{name|bold} {duration|dimmed}\n{artist|blue} · {album|magenta}";
+
+async fn concise_now_playing(backend: &impl PlayerBackend) -> Result<()> {
+    let track = backend
+        .current_track()
+        .await?
+        .ok_or_else(|| eyre!("Could not obtain current track"))?;
+
+    let template = Template::parse(CONCISE_TEMPLATE)?;
 
     println!(
-        "{} {}\n{} · {}",
-        name.bold(),
-        format::format_duration_plain(duration as i32).dimmed(),
-        artist.blue(),
-        album.magenta(),
+        "{}",
+        template.render(&template::Context {
+            state: backend.player_state().await?,
+            position: None,
+            track: Some(&track),
+            playlist: None,
+            volume: None,
+        })
     );
 
     Ok(())
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    color_eyre::install()?;
-
-    let args = Cli::parse();
+async fn run(
+    command: Commands,
+    backend: impl PlayerBackend + 'static,
+    config: Config,
+) -> Result<()> {
+    match command {
+        Commands::Play { query, first } => {
+            if query.is_empty() {
+                backend.play().await?;
+            } else {
+                cmd::play_query(&backend, &query.join(" "), first).await?;
+            }
 
-    match args.command {
-        Commands::Play => {
-            music::tell("play").await?;
             println!("{} playing music", "Started".green());
-            concise_now_playing().await?;
+            concise_now_playing(&backend).await?;
+        }
+
+        Commands::Search { query, limit } => {
+            cmd::search(&backend, &query.join(" "), limit).await?;
+        }
+
+        Commands::Playlist { command } => match command {
+            PlaylistCommands::List => cmd::playlist::list(&backend).await?,
+            PlaylistCommands::Show { name } => cmd::playlist::show(&backend, &name).await?,
+            PlaylistCommands::Play { name, shuffle } => {
+                cmd::playlist::play(&backend, &name, shuffle).await?;
+                println!("{} playing music", "Started".green());
+                concise_now_playing(&backend).await?;
+            }
+            PlaylistCommands::Create { name } => cmd::playlist::create(&backend, &name).await?,
+            PlaylistCommands::Add { name } => cmd::playlist::add(&backend, &name).await?,
+            PlaylistCommands::Remove { name } => cmd::playlist::remove(&backend, &name).await?,
+            PlaylistCommands::Export {
+                name,
+                format,
+                output,
+            } => cmd::playlist::export(&backend, &name, format, output.as_deref()).await?,
+        },
+
+        Commands::Queue { limit } => {
+            cmd::queue(&backend, limit).await?;
         }
 
         Commands::Pause => {
-            music::tell("pause").await?;
+            backend.pause().await?;
             println!("{} playing music", "Stopped".red());
-            concise_now_playing().await?;
+            concise_now_playing(&backend).await?;
         }
 
         Commands::Toggle => {
-            let player_state = music::tell("player state").await?;
+            backend.refresh().await?;
 
-            if player_state == "paused" {
-                music::tell("play").await?;
+            if backend.player_state().await? == PlayerState::Paused {
+                backend.play().await?;
                 println!("{} playing music", "Started".green());
             } else {
-                music::tell("pause").await?;
+                backend.pause().await?;
                 println!("{} playing music", "Stopped".red());
             }
 
-            concise_now_playing().await?;
+            concise_now_playing(&backend).await?;
         }
 
         Commands::Back => {
-            music::tell("back track").await?;
+            backend.back_track().await?;
             println!("{} to current or previous track", "Back tracked".cyan());
-            concise_now_playing().await?;
+            concise_now_playing(&backend).await?;
         }
 
         Commands::Forward => {
-            music::tell("fast forward").await?;
+            backend.fast_forward().await?;
             println!("{} in current track", "Fast forwarded".cyan());
-            concise_now_playing().await?;
+            concise_now_playing(&backend).await?;
+        }
+
+        Commands::Seek { target } => {
+            cmd::seek(&backend, target).await?;
+            concise_now_playing(&backend).await?;
         }
 
         Commands::Next => {
-            music::tell("next track").await?;
+            backend.next_track().await?;
             println!("{} to next track", "Advanced".magenta());
-            concise_now_playing().await?;
+            concise_now_playing(&backend).await?;
         }
 
         Commands::Previous => {
-            music::tell("previous track").await?;
+            backend.previous_track().await?;
             println!("{} to previous track", "Returned".magenta());
-            concise_now_playing().await?;
+            concise_now_playing(&backend).await?;
         }
 
         Commands::Resume => {
-            music::tell("resume").await?;
+            backend.resume().await?;
             println!("{} normal playback", "Resumed".magenta());
-            concise_now_playing().await?;
+            concise_now_playing(&backend).await?;
+        }
+
+        Commands::Shuffle { action } => {
+            cmd::shuffle(&backend, action).await?;
+        }
+
+        Commands::Repeat { action } => {
+            cmd::repeat(&backend, action).await?;
+        }
+
+        Commands::Volume { change } => {
+            cmd::volume(&backend, change).await?;
+        }
+
+        Commands::Love => {
+            cmd::love(&backend).await?;
+        }
+
+        Commands::Unlove => {
+            cmd::unlove(&backend).await?;
+        }
+
+        Commands::Dislike => {
+            cmd::dislike(&backend).await?;
+        }
+
+        Commands::Rate { rating } => {
+            cmd::rate(&backend, rating).await?;
         }
 
         Commands::Now(options) => {
-            cmd::now(options).await?;
+            cmd::now(backend, options, &config).await?;
+        }
+
+        Commands::Events { filter } => {
+            cmd::events(backend, &filter, &config).await?;
+        }
+
+        Commands::Lyrics => {
+            cmd::lyrics(&backend, &config.lyrics).await?;
         }
 
         Commands::SongLink => {
-            if let Some(track) = music::get_current_track().await? {
-                let metadata = music::fetch_metadata(&track).await?;
+            if let Some(track) = backend.current_track().await? {
+                let metadata = daemon::fetch_metadata(&track, &config.metadata).await?;
                 println!("{}", metadata.song_link);
             } else {
                 println!("{} playing music", "Not".red());
             }
         }
 
+        Commands::History { since, limit, json } => {
+            cmd::history(since, limit, json).await?;
+        }
+
+        Commands::Stats { period, json } => {
+            cmd::stats(period, json).await?;
+        }
+
+        Commands::Daemon => {
+            cmd::daemon(backend, &config).await?;
+        }
+
+        Commands::Scrobble { command } => match command {
+            Some(ScrobbleCommands::Auth) => cmd::scrobble::auth(&config.lastfm).await?,
+            None => cmd::scrobble::scrobble(&backend, &config).await?,
+        },
+
         Commands::Discord { command } => match command {
             Some(command) => match command {
                 DiscordCommands::Install => {
@@ -219,10 +503,21 @@ async fn main() -> Result<()> {
             },
 
             None => {
-                cmd::discord().await?;
+                cmd::discord(&backend, &config).await?;
             }
         },
 
+        Commands::Cache { command } => match command {
+            CacheCommands::Stats => cmd::cache::stats(&config.metadata).await?,
+            CacheCommands::Clear => cmd::cache::clear().await?,
+        },
+
+        Commands::Config { command } => match command {
+            ConfigCommands::Show => cmd::config::show()?,
+            ConfigCommands::Path => cmd::config::path()?,
+            ConfigCommands::Validate => cmd::config::validate()?,
+        },
+
         Commands::Completions { shell } => {
             let cli = &mut Cli::command();
             generate(shell, cli, cli.get_name().to_string(), &mut stdout());
@@ -231,3 +526,131 @@ async fn main() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    color_eyre::install()?;
+
+    let args = Cli::parse();
+
+    // Configuration problems shouldn't get in the way of diagnosing them.
+    let config = if let Commands::Config { .. } = args.command {
+        Config::default()
+    } else {
+        Config::load()?
+    };
+
+    // Go through the daemon if it is running, unless this is the daemon
+    if !matches!(args.command, Commands::Daemon)
+        && let Some(client) = daemon::Client::connect().await
+    {
+        let backend = daemon::DaemonBackend::new(client, music::AppleScript);
+        return run(args.command, backend, config).await;
+    }
+
+    run(args.command, music::AppleScript, config).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use music::{
+        RepeatMode,
+        fake::{ScriptedBackend, Snapshot, track},
+    };
+
+    async fn am(args: &[&str], backend: &ScriptedBackend) -> Result<()> {
+        let cli = Cli::try_parse_from(["am"].iter().chain(args))?;
+        run(cli.command, backend.clone(), Config::default()).await
+    }
+
+    #[tokio::test]
+    async fn play_and_pause() {
+        let backend =
+            ScriptedBackend::new(Snapshot::playing(track("1", "One")).state(PlayerState::Paused));
+
+        am(&["play"], &backend).await.unwrap();
+        assert_eq!(backend.snapshot().state, PlayerState::Playing);
+
+        am(&["pause"], &backend).await.unwrap();
+        assert_eq!(backend.snapshot().state, PlayerState::Paused);
+
+        assert_eq!(backend.commands(), ["play", "pause"]);
+    }
+
+    #[tokio::test]
+    async fn toggle() {
+        let backend =
+            ScriptedBackend::new(Snapshot::playing(track("1", "One")).state(PlayerState::Paused));
+
+        am(&["toggle"], &backend).await.unwrap();
+        am(&["toggle"], &backend).await.unwrap();
+
+        assert_eq!(backend.commands(), ["play", "pause"]);
+    }
+
+    #[tokio::test]
+    async fn next_moves_on_to_the_next_track() {
+        let backend = ScriptedBackend::new(Snapshot::playing(track("1", "One")))
+            .then(Snapshot::playing(track("2", "Two")));
+
+        am(&["next"], &backend).await.unwrap();
+
+        assert_eq!(backend.commands(), ["next track"]);
+        assert_eq!(backend.snapshot().track.unwrap().id, "2");
+    }
+
+    #[tokio::test]
+    async fn cycle_modes() {
+        let backend = ScriptedBackend::new(Snapshot::playing(track("1", "One")));
+
+        am(&["shuffle", "toggle"], &backend).await.unwrap();
+        am(&["repeat", "cycle"], &backend).await.unwrap();
+
+        let snapshot = backend.snapshot();
+        assert!(snapshot.shuffle_enabled);
+        assert_eq!(snapshot.repeat_mode, RepeatMode::All);
+        assert_eq!(
+            backend.commands(),
+            ["set shuffle enabled", "set repeat mode"]
+        );
+    }
+
+    #[tokio::test]
+    async fn play_query() {
+        let backend = ScriptedBackend::new(Snapshot::stopped())
+            .library(vec![track("1", "One"), track("2", "Two")], Vec::new());
+
+        am(&["play", "--first", "Two"], &backend).await.unwrap();
+
+        assert_eq!(backend.commands(), ["play track"]);
+        assert_eq!(backend.snapshot().track.unwrap().id, "2");
+    }
+
+    #[tokio::test]
+    async fn love_clears_dislike() {
+        let mut disliked = track("1", "One");
+        disliked.disliked = true;
+        let backend = ScriptedBackend::new(Snapshot::playing(disliked));
+
+        am(&["love"], &backend).await.unwrap();
+        am(&["rate", "4"], &backend).await.unwrap();
+
+        let track = backend.snapshot().track.unwrap();
+        assert!(track.favorited && !track.disliked);
+        assert_eq!(track.rating, 4);
+
+        assert!(am(&["rate", "6"], &backend).await.is_err());
+    }
+
+    #[test]
+    fn snapshots_cant_be_filtered() {
+        let cli = Cli::try_parse_from(["am", "events", "--filter", "seeked,quit"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Events { filter } if filter == [events::EventKind::Seeked, events::EventKind::Quit]
+        ));
+
+        assert!(Cli::try_parse_from(["am", "events", "--filter", "snapshot"]).is_err());
+    }
+}
@@ -0,0 +1,466 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{path::PathBuf, process::Stdio};
+use tokio::process::Command;
+
+use async_trait::async_trait;
+use eyre::{Result, bail, eyre};
+
+use super::{
+    PlayerBackend, PlayerState, Playlist, RepeatMode, ShuffleMode, Track, quote, tell, tell_raw,
+};
+
+/// Separates the fields of a record in script output.
+const UNIT_SEPARATOR: char = '\u{1f}';
+/// Separates records in script output.
+const RECORD_SEPARATOR: char = '\u{1e}';
+
+/// Statements that append the track `t` to `output` as a record for [`parse_tracks`].
+///
+/// Only tracks stored as files have a location, so looking it up is allowed to fail.
+const TRACK_RECORD: &[&str] = &[
+    r#"set t_location to """#,
+    r"try",
+    r"set t_location to POSIX path of (location of t)",
+    r"end try",
+    r"set output to output & (database id of t as text) & (character id 31) & name of t & (character id 31) & album of t & (character id 31) & artist of t & (character id 31) & (duration of t as text) & (character id 31) & t_location & (character id 31) & (favorited of t as text) & (character id 31) & (disliked of t as text) & (character id 31) & (rating of t as text) & (character id 30)",
+];
+
+/// An expression that serializes the playlist `p` into a record for [`parse_playlists`].
+const PLAYLIST_RECORD: &str = "name of p & (character id 31) & (duration of p as text) & (character id 31) & (count of tracks of p as text) & (character id 30)";
+
+fn records(output: &str) -> impl Iterator<Item = Vec<&str>> {
+    output
+        .split(RECORD_SEPARATOR)
+        .filter(|record| !record.trim().is_empty())
+        .map(|record| {
+            record
+                .trim_start_matches('\n')
+                .split(UNIT_SEPARATOR)
+                .collect()
+        })
+}
+
+fn parse_tracks(output: &str) -> Result<Vec<Track>> {
+    records(output)
+        .map(|fields| {
+            let [
+                id,
+                name,
+                album,
+                artist,
+                duration,
+                location,
+                favorited,
+                disliked,
+                rating,
+            ] = fields[..]
+            else {
+                return Err(eyre!("Could not parse track {fields:?}"));
+            };
+
+            Ok(Track {
+                id: id.to_owned(),
+                name: name.to_owned(),
+                album: album.to_owned(),
+                artist: artist.to_owned(),
+                duration: duration
+                    .replace(',', ".")
+                    .parse::<f64>()
+                    .unwrap_or_default(),
+                location: Some(location)
+                    .filter(|location| !location.is_empty())
+                    .map(PathBuf::from),
+                favorited: favorited == "true",
+                disliked: disliked == "true",
+                // Music stores ratings out of 100, in steps of 20 per star
+                rating: rating.parse::<u8>().map(|r| r / 20).unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+fn parse_playlists(output: &str) -> Result<Vec<Playlist>> {
+    records(output)
+        .map(|fields| {
+            let [name, duration, track_count] = fields[..] else {
+                return Err(eyre!("Could not parse playlist {fields:?}"));
+            };
+
+            Ok(Playlist {
+                name: name.to_owned(),
+                duration: duration.parse::<i32>().unwrap_or_default(),
+                track_count: track_count.parse::<usize>().unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+/// Database IDs are interpolated into scripts, so make sure they are numeric.
+fn parse_id(id: &str) -> Result<u64> {
+    id.parse::<u64>()
+        .map_err(|_| eyre!("Invalid track ID {id:?}"))
+}
+
+/// Controls Music.app by running scripts through `osascript`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AppleScript;
+
+#[async_trait]
+impl PlayerBackend for AppleScript {
+    async fn is_running(&self) -> Result<bool> {
+        Ok(Command::new("pgrep")
+            .arg(r"^Music$")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .await?
+            .success())
+    }
+
+    async fn player_state(&self) -> Result<PlayerState> {
+        tell("get player state").await?.parse::<PlayerState>()
+    }
+
+    async fn player_position(&self) -> Result<Option<f64>> {
+        Ok(tell("get player position")
+            .await?
+            .replace(',', ".")
+            .parse::<f64>()
+            .ok())
+    }
+
+    async fn set_player_position(&self, position: f64) -> Result<()> {
+        tell(&format!("set player position to {position:.3}")).await?;
+        Ok(())
+    }
+
+    async fn current_track_id(&self) -> Result<Option<String>> {
+        let id = tell_raw(&[
+            r#"tell application "Music""#,
+            r#"if player state is stopped then return """#,
+            r"return database id of current track",
+            r"end tell",
+        ])
+        .await?;
+
+        Ok(Some(id).filter(|id| !id.is_empty()))
+    }
+
+    async fn current_track(&self) -> Result<Option<Track>> {
+        let script = [
+            &[
+                r#"set output to """#,
+                r#"tell application "Music""#,
+                r"if player state is stopped then return output",
+                r"set t to current track",
+            ],
+            TRACK_RECORD,
+            &[r"end tell", r"return output"],
+        ]
+        .concat();
+
+        Ok(parse_tracks(&tell_raw(&script).await?)?.into_iter().next())
+    }
+
+    async fn current_track_lyrics(&self) -> Result<Option<String>> {
+        let lyrics = tell_raw(&[
+            r#"tell application "Music""#,
+            r#"if player state is stopped then return """#,
+            r"try",
+            r"return lyrics of current track",
+            r"on error",
+            r#"return """#,
+            r"end try",
+            r"end tell",
+        ])
+        .await?;
+
+        // Lyrics use classic Mac line endings
+        let lyrics = lyrics.replace("\r\n", "\n").replace('\r', "\n");
+
+        Ok(Some(lyrics).filter(|lyrics| !lyrics.trim().is_empty()))
+    }
+
+    async fn current_playlist(&self) -> Result<Option<Playlist>> {
+        let record = format!("set output to output & {PLAYLIST_RECORD}");
+
+        let output = tell_raw(&[
+            r#"set output to """#,
+            r#"tell application "Music""#,
+            r"try",
+            r"set p to current playlist",
+            r"on error",
+            r"return output",
+            r"end try",
+            &record,
+            r"end tell",
+            r"return output",
+        ])
+        .await?;
+
+        Ok(parse_playlists(&output)?.into_iter().next())
+    }
+
+    async fn upcoming_tracks(&self, limit: usize) -> Result<Vec<Track>> {
+        let range = format!(
+            "repeat with i from (index of current track + 1) to (index of current track + {limit})"
+        );
+
+        let script = [
+            &[
+                r#"set output to """#,
+                r#"tell application "Music""#,
+                r"if player state is stopped then return output",
+                r"try",
+                r"set p to current playlist",
+                r"on error",
+                r"return output",
+                r"end try",
+                &range,
+                r"if i > (count of tracks of p) then exit repeat",
+                r"set t to track i of p",
+            ],
+            TRACK_RECORD,
+            &[r"end repeat", r"end tell", r"return output"],
+        ]
+        .concat();
+
+        parse_tracks(&tell_raw(&script).await?)
+    }
+
+    async fn play_upcoming(&self, offset: usize) -> Result<()> {
+        if self.current_playlist().await?.is_none() {
+            bail!("Nothing is playing from a playlist");
+        }
+
+        tell(&format!(
+            "play track (index of current track + {offset}) of current playlist"
+        ))
+        .await?;
+        Ok(())
+    }
+
+    async fn set_favorited(&self, favorited: bool) -> Result<()> {
+        tell(&format!("set favorited of current track to {favorited}")).await?;
+        Ok(())
+    }
+
+    async fn set_disliked(&self, disliked: bool) -> Result<()> {
+        tell(&format!("set disliked of current track to {disliked}")).await?;
+        Ok(())
+    }
+
+    async fn set_rating(&self, rating: u8) -> Result<()> {
+        tell(&format!(
+            "set rating of current track to {}",
+            u16::from(rating.min(5)) * 20
+        ))
+        .await?;
+        Ok(())
+    }
+
+    async fn volume(&self) -> Result<u8> {
+        Ok(tell("get sound volume").await?.parse::<u8>()?)
+    }
+
+    async fn set_volume(&self, volume: u8) -> Result<()> {
+        tell(&format!("set sound volume to {}", volume.min(100))).await?;
+        Ok(())
+    }
+
+    async fn shuffle_enabled(&self) -> Result<bool> {
+        Ok(tell("get shuffle enabled").await?.parse::<bool>()?)
+    }
+
+    async fn set_shuffle_enabled(&self, enabled: bool) -> Result<()> {
+        tell(&format!("set shuffle enabled to {enabled}")).await?;
+        Ok(())
+    }
+
+    async fn shuffle_mode(&self) -> Result<ShuffleMode> {
+        tell("get shuffle mode").await?.parse::<ShuffleMode>()
+    }
+
+    async fn set_shuffle_mode(&self, mode: ShuffleMode) -> Result<()> {
+        tell(&format!("set shuffle mode to {mode}")).await?;
+        Ok(())
+    }
+
+    async fn repeat_mode(&self) -> Result<RepeatMode> {
+        tell("get song repeat").await?.parse::<RepeatMode>()
+    }
+
+    async fn set_repeat_mode(&self, mode: RepeatMode) -> Result<()> {
+        tell(&format!("set song repeat to {mode}")).await?;
+        Ok(())
+    }
+
+    async fn search_tracks(&self, query: &str, limit: usize) -> Result<Vec<Track>> {
+        let search = format!(
+            "set results to search library playlist 1 for {}",
+            quote(query)
+        );
+        let limit = format!("if i > {limit} then exit repeat");
+
+        let script = [
+            &[
+                r#"set output to """#,
+                r#"tell application "Music""#,
+                &search,
+                r"if results is missing value then set results to {}",
+                r"repeat with i from 1 to (count of results)",
+                &limit,
+                r"set t to item i of results",
+            ],
+            TRACK_RECORD,
+            &[r"end repeat", r"end tell", r"return output"],
+        ]
+        .concat();
+
+        let output = tell_raw(&script).await?;
+
+        parse_tracks(&output)
+    }
+
+    async fn search_playlists(&self, query: &str) -> Result<Vec<Playlist>> {
+        let search = format!(
+            "repeat with p in (every user playlist whose name contains {})",
+            quote(query)
+        );
+        let record = format!("set output to output & {PLAYLIST_RECORD}");
+
+        let output = tell_raw(&[
+            r#"set output to """#,
+            r#"tell application "Music""#,
+            &search,
+            &record,
+            r"end repeat",
+            r"end tell",
+            r"return output",
+        ])
+        .await?;
+
+        parse_playlists(&output)
+    }
+
+    async fn playlists(&self) -> Result<Vec<Playlist>> {
+        let record = format!("set output to output & {PLAYLIST_RECORD}");
+
+        let output = tell_raw(&[
+            r#"set output to """#,
+            r#"tell application "Music""#,
+            r"repeat with p in (every user playlist whose special kind is none)",
+            &record,
+            r"end repeat",
+            r"end tell",
+            r"return output",
+        ])
+        .await?;
+
+        parse_playlists(&output)
+    }
+
+    async fn playlist_tracks(&self, name: &str) -> Result<Vec<Track>> {
+        let tracks = format!(
+            "repeat with t in (every track of user playlist {})",
+            quote(name)
+        );
+
+        let script = [
+            &[
+                r#"set output to """#,
+                r#"tell application "Music""#,
+                &tracks,
+            ],
+            TRACK_RECORD,
+            &[r"end repeat", r"end tell", r"return output"],
+        ]
+        .concat();
+
+        parse_tracks(&tell_raw(&script).await?)
+    }
+
+    async fn create_playlist(&self, name: &str) -> Result<()> {
+        tell(&format!(
+            "make new user playlist with properties {{name:{}}}",
+            quote(name)
+        ))
+        .await?;
+        Ok(())
+    }
+
+    async fn add_to_playlist(&self, name: &str, track_id: &str) -> Result<()> {
+        let track_id = parse_id(track_id)?;
+
+        tell(&format!(
+            "duplicate (first track of library playlist 1 whose database id is {track_id}) to user playlist {}",
+            quote(name)
+        ))
+        .await?;
+        Ok(())
+    }
+
+    async fn remove_from_playlist(&self, name: &str, track_id: &str) -> Result<()> {
+        let track_id = parse_id(track_id)?;
+
+        tell(&format!(
+            "delete (every track of user playlist {} whose database id is {track_id})",
+            quote(name)
+        ))
+        .await?;
+        Ok(())
+    }
+
+    async fn play_track(&self, id: &str) -> Result<()> {
+        let id = parse_id(id)?;
+
+        tell(&format!(
+            "play (first track of library playlist 1 whose database id is {id})"
+        ))
+        .await?;
+        Ok(())
+    }
+
+    async fn play_playlist(&self, name: &str) -> Result<()> {
+        tell(&format!("play playlist {}", quote(name))).await?;
+        Ok(())
+    }
+
+    async fn play(&self) -> Result<()> {
+        tell("play").await?;
+        Ok(())
+    }
+
+    async fn pause(&self) -> Result<()> {
+        tell("pause").await?;
+        Ok(())
+    }
+
+    async fn back_track(&self) -> Result<()> {
+        tell("back track").await?;
+        Ok(())
+    }
+
+    async fn fast_forward(&self) -> Result<()> {
+        tell("fast forward").await?;
+        Ok(())
+    }
+
+    async fn next_track(&self) -> Result<()> {
+        tell("next track").await?;
+        Ok(())
+    }
+
+    async fn previous_track(&self) -> Result<()> {
+        tell("previous track").await?;
+        Ok(())
+    }
+
+    async fn resume(&self) -> Result<()> {
+        tell("resume").await?;
+        Ok(())
+    }
+}
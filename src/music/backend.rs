@@ -0,0 +1,114 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use async_trait::async_trait;
+use eyre::Result;
+
+use super::{PlayerState, Playlist, RepeatMode, ShuffleMode, Track};
+
+/// A music player that can be queried for its playback state and sent transport commands.
+///
+/// [`AppleScript`](super::AppleScript) is the implementation used in practice, which
+/// controls Music.app through `osascript`.
+#[async_trait]
+pub trait PlayerBackend: Send + Sync {
+    /// Whether the player application is currently running.
+    async fn is_running(&self) -> Result<bool>;
+
+    /// The current player state.
+    async fn player_state(&self) -> Result<PlayerState>;
+
+    /// The position in the current track in seconds, if there is a current track.
+    async fn player_position(&self) -> Result<Option<f64>>;
+    /// Set the position in the current track in seconds.
+    async fn set_player_position(&self, position: f64) -> Result<()>;
+
+    /// The database ID of the current track, if there is a current track.
+    ///
+    /// This is cheaper than [`current_track`](Self::current_track) and can be used to
+    /// detect track changes.
+    async fn current_track_id(&self) -> Result<Option<String>>;
+
+    /// The current track, or `None` if playback is stopped.
+    async fn current_track(&self) -> Result<Option<Track>>;
+
+    /// The lyrics stored with the current track, if there are any.
+    async fn current_track_lyrics(&self) -> Result<Option<String>>;
+
+    /// The current playlist, if there is one.
+    async fn current_playlist(&self) -> Result<Option<Playlist>>;
+
+    /// Favorite or unfavorite the current track.
+    async fn set_favorited(&self, favorited: bool) -> Result<()>;
+    /// Mark the current track as disliked, or clear the mark.
+    async fn set_disliked(&self, disliked: bool) -> Result<()>;
+    /// Set the star rating of the current track, from 0 to 5.
+    async fn set_rating(&self, rating: u8) -> Result<()>;
+
+    /// Up to `limit` tracks that follow the current track in the current playlist, in
+    /// playlist order, or none if nothing is playing from a playlist.
+    async fn upcoming_tracks(&self, limit: usize) -> Result<Vec<Track>>;
+    /// Play the track `offset` tracks after the current track in the current playlist.
+    async fn play_upcoming(&self, offset: usize) -> Result<()>;
+
+    /// The player volume, from 0 to 100.
+    async fn volume(&self) -> Result<u8>;
+    /// Set the player volume, from 0 to 100.
+    async fn set_volume(&self, volume: u8) -> Result<()>;
+
+    /// Whether shuffle is enabled.
+    async fn shuffle_enabled(&self) -> Result<bool>;
+    /// Enable or disable shuffle.
+    async fn set_shuffle_enabled(&self, enabled: bool) -> Result<()>;
+    /// What is shuffled when shuffle is enabled.
+    async fn shuffle_mode(&self) -> Result<ShuffleMode>;
+    /// Set what is shuffled when shuffle is enabled.
+    async fn set_shuffle_mode(&self, mode: ShuffleMode) -> Result<()>;
+    /// The repeat mode.
+    async fn repeat_mode(&self) -> Result<RepeatMode>;
+    /// Set the repeat mode.
+    async fn set_repeat_mode(&self, mode: RepeatMode) -> Result<()>;
+
+    /// Search the library for tracks matching `query` by name, album, artist and so on,
+    /// returning at most `limit` tracks.
+    async fn search_tracks(&self, query: &str, limit: usize) -> Result<Vec<Track>>;
+    /// Find playlists whose name contains `query`.
+    async fn search_playlists(&self, query: &str) -> Result<Vec<Playlist>>;
+    /// The playlists created by the user, excluding folders.
+    async fn playlists(&self) -> Result<Vec<Playlist>>;
+    /// The tracks in the user playlist with the given name, in order.
+    async fn playlist_tracks(&self, name: &str) -> Result<Vec<Track>>;
+    /// Create an empty user playlist with the given name.
+    async fn create_playlist(&self, name: &str) -> Result<()>;
+    /// Add the library track with the given database ID to the end of a user playlist.
+    async fn add_to_playlist(&self, name: &str, track_id: &str) -> Result<()>;
+    /// Remove every occurrence of the track with the given database ID from a user playlist.
+    async fn remove_from_playlist(&self, name: &str, track_id: &str) -> Result<()>;
+
+    /// Play the library track with the given database ID.
+    async fn play_track(&self, id: &str) -> Result<()>;
+    /// Play the playlist with the given name.
+    async fn play_playlist(&self, name: &str) -> Result<()>;
+
+    /// Start playback.
+    async fn play(&self) -> Result<()>;
+    /// Pause playback.
+    async fn pause(&self) -> Result<()>;
+    /// Reposition to the beginning of the current track, or go to the previous track if already at the start.
+    async fn back_track(&self) -> Result<()>;
+    /// Skip forward in the current track.
+    async fn fast_forward(&self) -> Result<()>;
+    /// Advance to the next track in the current playlist.
+    async fn next_track(&self) -> Result<()>;
+    /// Return to the previous track in the current playlist.
+    async fn previous_track(&self) -> Result<()>;
+    /// Disable fast forward/rewind and resume playback.
+    async fn resume(&self) -> Result<()>;
+
+    /// Bring the playback state up to date for backends that cache it, before querying it to
+    /// decide what to change.
+    async fn refresh(&self) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,208 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Metadata found for tracks, cached in `$XDG_CACHE_HOME/am/metadata.json` so that the
+//! catalog is searched once per track rather than on every update.
+
+use std::{collections::HashMap, path::PathBuf};
+use tokio::fs;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{Metadata, Track};
+use crate::{config::MetadataConfig, dirs};
+
+/// The metadata found for a track, or the lack of it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    name: String,
+    album: String,
+    artist: String,
+    storefront: String,
+    /// `None` if the catalog has no match for the track
+    pub metadata: Option<Metadata>,
+    pub fetched_at: DateTime<Utc>,
+    used_at: DateTime<Utc>,
+}
+
+impl Entry {
+    /// Whether the entry was made for `track` as it is now, which may have been edited
+    /// since.
+    fn matches(&self, track: &Track, config: &MetadataConfig) -> bool {
+        self.name == track.name
+            && self.album == track.album
+            && self.artist == track.artist
+            && self.storefront == config.storefront
+    }
+
+    pub fn is_expired(&self, config: &MetadataConfig) -> bool {
+        let ttl = if self.metadata.is_some() {
+            TimeDelta::days(i64::from(config.cache_ttl_days))
+        } else {
+            TimeDelta::hours(i64::from(config.negative_cache_ttl_hours))
+        };
+
+        Utc::now() - self.fetched_at > ttl
+    }
+}
+
+/// Entries by track ID, evicting the least recently used ones beyond
+/// [`cache_size`](MetadataConfig::cache_size).
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Cache {
+    entries: HashMap<String, Entry>,
+}
+
+impl Cache {
+    pub fn path() -> Result<PathBuf> {
+        Ok(dirs::cache_dir()?.join("metadata.json"))
+    }
+
+    /// Load the cache, starting over if it can't be read.
+    pub async fn load() -> Result<Self> {
+        match fs::read(Self::path()?).await {
+            Ok(data) => Ok(serde_json::from_slice(&data).unwrap_or_default()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub async fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        // Other processes may be reading the cache at the same time
+        let temp = path.with_extension(format!("json.{}", std::process::id()));
+        fs::write(&temp, serde_json::to_vec(self)?).await?;
+        fs::rename(&temp, &path).await?;
+
+        Ok(())
+    }
+
+    pub async fn clear() -> Result<()> {
+        match fs::remove_file(Self::path()?).await {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.values()
+    }
+
+    /// The entry for `track` if it is still fresh and confident enough, marking it as used.
+    pub fn get(&mut self, track: &Track, config: &MetadataConfig) -> Option<&Entry> {
+        let entry = self.entries.get_mut(&track.id)?;
+
+        // Entries from before matches were scored have no confidence at all
+        let unconfident = entry
+            .metadata
+            .as_ref()
+            .is_some_and(|metadata| metadata.confidence < config.min_confidence);
+
+        if !entry.matches(track, config) || entry.is_expired(config) || unconfident {
+            return None;
+        }
+
+        entry.used_at = Utc::now();
+        Some(entry)
+    }
+
+    /// Cache what was found for `track`, dropping expired entries and evicting the least
+    /// recently used ones to make room.
+    pub fn insert(&mut self, track: &Track, metadata: Option<Metadata>, config: &MetadataConfig) {
+        let now = Utc::now();
+
+        self.entries.insert(
+            track.id.clone(),
+            Entry {
+                name: track.name.clone(),
+                album: track.album.clone(),
+                artist: track.artist.clone(),
+                storefront: config.storefront.clone(),
+                metadata,
+                fetched_at: now,
+                used_at: now,
+            },
+        );
+
+        self.entries.retain(|_, entry| !entry.is_expired(config));
+
+        if self.entries.len() > config.cache_size {
+            let mut ids = self
+                .entries
+                .iter()
+                .map(|(id, entry)| (entry.used_at, id.clone()))
+                .collect::<Vec<_>>();
+            ids.sort();
+
+            let excess = self.entries.len() - config.cache_size;
+            for (_, id) in ids.into_iter().take(excess) {
+                self.entries.remove(&id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track() -> Track {
+        Track {
+            id: "1".into(),
+            name: "Bohemian Rhapsody".into(),
+            album: "A Night at the Opera".into(),
+            artist: "Queen".into(),
+            duration: 354.3,
+            location: None,
+            favorited: false,
+            disliked: false,
+            rating: 0,
+        }
+    }
+
+    fn metadata(confidence: f64) -> Metadata {
+        Metadata {
+            album_artwork: String::new(),
+            artist_artwork: None,
+            share_url: String::new(),
+            song_link: String::new(),
+            confidence,
+        }
+    }
+
+    #[test]
+    fn misses_unconfident_entries() {
+        let config = MetadataConfig::default();
+        let mut cache = Cache::default();
+
+        cache.insert(&track(), Some(metadata(0.9)), &config);
+        assert!(cache.get(&track(), &config).is_some());
+
+        cache.insert(&track(), Some(metadata(0.3)), &config);
+        assert!(cache.get(&track(), &config).is_none());
+
+        // Unmatched tracks have no confidence to speak of
+        cache.insert(&track(), None, &config);
+        assert!(cache.get(&track(), &config).is_some());
+    }
+
+    #[test]
+    fn misses_edited_tracks() {
+        let config = MetadataConfig::default();
+        let mut cache = Cache::default();
+
+        cache.insert(&track(), Some(metadata(0.9)), &config);
+
+        let mut edited = track();
+        edited.name = "Bohemian Rhapsody (Live)".into();
+        assert!(cache.get(&edited, &config).is_none());
+    }
+}
@@ -0,0 +1,651 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! An in-memory [`PlayerBackend`] that plays back a script of player snapshots,
+//! so that commands can be exercised without Music.app.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use async_trait::async_trait;
+use eyre::{Result, eyre};
+
+use super::{PlayerBackend, PlayerState, Playlist, RepeatMode, ShuffleMode, Track};
+
+/// A three-minute track called `name` on a placeholder album and artist.
+pub fn track(id: &str, name: &str) -> Track {
+    Track {
+        id: id.to_owned(),
+        name: name.to_owned(),
+        album: "Album".to_owned(),
+        artist: "Artist".to_owned(),
+        duration: 180.,
+        location: None,
+        favorited: false,
+        disliked: false,
+        rating: 0,
+    }
+}
+
+/// What the player looks like at one step of a script.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub running: bool,
+    pub state: PlayerState,
+    pub position: Option<f64>,
+    pub track: Option<Track>,
+    pub lyrics: Option<String>,
+    pub playlist: Option<Playlist>,
+    pub volume: u8,
+    pub shuffle_enabled: bool,
+    pub shuffle_mode: ShuffleMode,
+    pub repeat_mode: RepeatMode,
+}
+
+impl Snapshot {
+    /// A running player with nothing queued.
+    pub fn stopped() -> Self {
+        Self {
+            running: true,
+            state: PlayerState::Stopped,
+            position: None,
+            track: None,
+            lyrics: None,
+            playlist: None,
+            volume: 100,
+            shuffle_enabled: false,
+            shuffle_mode: ShuffleMode::Songs,
+            repeat_mode: RepeatMode::Off,
+        }
+    }
+
+    /// A running player that is playing `track` from the beginning.
+    pub fn playing(track: Track) -> Self {
+        Self {
+            running: true,
+            state: PlayerState::Playing,
+            position: Some(0.),
+            track: Some(track),
+            lyrics: None,
+            playlist: None,
+            volume: 100,
+            shuffle_enabled: false,
+            shuffle_mode: ShuffleMode::Songs,
+            repeat_mode: RepeatMode::Off,
+        }
+    }
+
+    #[must_use]
+    pub fn state(mut self, state: PlayerState) -> Self {
+        self.state = state;
+        self
+    }
+
+    #[must_use]
+    pub fn position(mut self, position: f64) -> Self {
+        self.position = Some(position);
+        self
+    }
+
+    #[must_use]
+    pub fn lyrics(mut self, lyrics: &str) -> Self {
+        self.lyrics = Some(lyrics.to_owned());
+        self
+    }
+
+    #[must_use]
+    pub fn playlist(mut self, playlist: Playlist) -> Self {
+        self.playlist = Some(playlist);
+        self
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    library: Vec<Track>,
+    playlists: Vec<Playlist>,
+    playlist_tracks: HashMap<String, Vec<Track>>,
+    current: Snapshot,
+    script: VecDeque<Snapshot>,
+    commands: Vec<&'static str>,
+}
+
+/// A scripted player backend.
+///
+/// Queries return the current [`Snapshot`]. Transport commands are recorded and
+/// applied to the current snapshot; track-changing commands (next, previous and
+/// back) move on to the next scripted snapshot, as does [`advance`](Self::advance).
+///
+/// Clones share the same state.
+#[derive(Debug, Clone)]
+pub struct ScriptedBackend {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl ScriptedBackend {
+    pub fn new(initial: Snapshot) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                library: Vec::new(),
+                playlists: Vec::new(),
+                playlist_tracks: HashMap::new(),
+                current: initial,
+                script: VecDeque::new(),
+                commands: Vec::new(),
+            })),
+        }
+    }
+
+    /// Queue a snapshot to move on to after the ones already queued.
+    #[must_use]
+    pub fn then(self, next: Snapshot) -> Self {
+        self.lock().script.push_back(next);
+        self
+    }
+
+    /// Set the tracks and playlists in the library, for searching and playing.
+    #[must_use]
+    pub fn library(self, tracks: Vec<Track>, playlists: Vec<Playlist>) -> Self {
+        {
+            let mut inner = self.lock();
+            inner.library = tracks;
+            inner.playlists = playlists;
+        }
+        self
+    }
+
+    /// Set the tracks in a playlist, adding the playlist to the library if needed.
+    #[must_use]
+    pub fn playlist_tracks(self, playlist: Playlist, tracks: Vec<Track>) -> Self {
+        {
+            let mut inner = self.lock();
+
+            if !inner.playlists.iter().any(|p| p.name == playlist.name) {
+                inner.playlists.push(playlist.clone());
+            }
+
+            inner.playlist_tracks.insert(playlist.name, tracks);
+        }
+        self
+    }
+
+    /// Move on to the next scripted snapshot, returning `false` if the script is exhausted.
+    pub fn advance(&self) -> bool {
+        let mut inner = self.lock();
+
+        if let Some(next) = inner.script.pop_front() {
+            inner.current = next;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// The current snapshot.
+    pub fn snapshot(&self) -> Snapshot {
+        self.lock().current.clone()
+    }
+
+    /// The transport commands received so far, in order.
+    pub fn commands(&self) -> Vec<&'static str> {
+        self.lock().commands.clone()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap()
+    }
+
+    /// Apply `f` to the current track and its copy in the library, if any.
+    fn current_track_mut(&self, f: impl Fn(&mut Track)) -> Result<()> {
+        let mut inner = self.lock();
+        let inner = &mut *inner;
+
+        let track = inner
+            .current
+            .track
+            .as_mut()
+            .ok_or_else(|| eyre!("No current track"))?;
+        f(track);
+
+        if let Some(library_track) = inner.library.iter_mut().find(|t| t.id == track.id) {
+            f(library_track);
+        }
+
+        Ok(())
+    }
+
+    fn record(&self, command: &'static str, state: Option<PlayerState>) {
+        let mut inner = self.lock();
+        inner.commands.push(command);
+
+        if let Some(state) = state {
+            inner.current.state = state;
+        }
+    }
+}
+
+#[async_trait]
+impl PlayerBackend for ScriptedBackend {
+    async fn is_running(&self) -> Result<bool> {
+        Ok(self.lock().current.running)
+    }
+
+    async fn player_state(&self) -> Result<PlayerState> {
+        Ok(self.lock().current.state)
+    }
+
+    async fn player_position(&self) -> Result<Option<f64>> {
+        Ok(self.lock().current.position)
+    }
+
+    async fn set_player_position(&self, position: f64) -> Result<()> {
+        self.record("set player position", None);
+        self.lock().current.position = Some(position);
+        Ok(())
+    }
+
+    async fn current_track_id(&self) -> Result<Option<String>> {
+        let inner = self.lock();
+
+        if inner.current.state == PlayerState::Stopped {
+            Ok(None)
+        } else {
+            Ok(inner.current.track.as_ref().map(|t| t.id.clone()))
+        }
+    }
+
+    async fn current_track(&self) -> Result<Option<Track>> {
+        let inner = self.lock();
+
+        if inner.current.state == PlayerState::Stopped {
+            Ok(None)
+        } else {
+            Ok(inner.current.track.clone())
+        }
+    }
+
+    async fn current_track_lyrics(&self) -> Result<Option<String>> {
+        let inner = self.lock();
+
+        if inner.current.state == PlayerState::Stopped {
+            Ok(None)
+        } else {
+            Ok(inner.current.lyrics.clone())
+        }
+    }
+
+    async fn current_playlist(&self) -> Result<Option<Playlist>> {
+        Ok(self.lock().current.playlist.clone())
+    }
+
+    async fn upcoming_tracks(&self, limit: usize) -> Result<Vec<Track>> {
+        let inner = self.lock();
+        let (Some(track), Some(playlist)) = (&inner.current.track, &inner.current.playlist) else {
+            return Ok(Vec::new());
+        };
+
+        let tracks = inner
+            .playlist_tracks
+            .get(&playlist.name)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        Ok(tracks
+            .iter()
+            .skip_while(|t| t.id != track.id)
+            .skip(1)
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
+    async fn play_upcoming(&self, offset: usize) -> Result<()> {
+        self.record("play upcoming", Some(PlayerState::Playing));
+
+        let track = self
+            .upcoming_tracks(offset)
+            .await?
+            .into_iter()
+            .nth(offset.wrapping_sub(1))
+            .ok_or_else(|| eyre!("No track {offset} tracks after the current track"))?;
+
+        let mut inner = self.lock();
+        inner.current.position = Some(0.);
+        inner.current.track = Some(track);
+        Ok(())
+    }
+
+    async fn set_favorited(&self, favorited: bool) -> Result<()> {
+        self.record("set favorited", None);
+        self.current_track_mut(|track| track.favorited = favorited)
+    }
+
+    async fn set_disliked(&self, disliked: bool) -> Result<()> {
+        self.record("set disliked", None);
+        self.current_track_mut(|track| track.disliked = disliked)
+    }
+
+    async fn set_rating(&self, rating: u8) -> Result<()> {
+        self.record("set rating", None);
+        self.current_track_mut(|track| track.rating = rating.min(5))
+    }
+
+    async fn volume(&self) -> Result<u8> {
+        Ok(self.lock().current.volume)
+    }
+
+    async fn set_volume(&self, volume: u8) -> Result<()> {
+        self.record("set volume", None);
+        self.lock().current.volume = volume.min(100);
+        Ok(())
+    }
+
+    async fn shuffle_enabled(&self) -> Result<bool> {
+        Ok(self.lock().current.shuffle_enabled)
+    }
+
+    async fn set_shuffle_enabled(&self, enabled: bool) -> Result<()> {
+        self.record("set shuffle enabled", None);
+        self.lock().current.shuffle_enabled = enabled;
+        Ok(())
+    }
+
+    async fn shuffle_mode(&self) -> Result<ShuffleMode> {
+        Ok(self.lock().current.shuffle_mode)
+    }
+
+    async fn set_shuffle_mode(&self, mode: ShuffleMode) -> Result<()> {
+        self.record("set shuffle mode", None);
+        self.lock().current.shuffle_mode = mode;
+        Ok(())
+    }
+
+    async fn repeat_mode(&self) -> Result<RepeatMode> {
+        Ok(self.lock().current.repeat_mode)
+    }
+
+    async fn set_repeat_mode(&self, mode: RepeatMode) -> Result<()> {
+        self.record("set repeat mode", None);
+        self.lock().current.repeat_mode = mode;
+        Ok(())
+    }
+
+    async fn search_tracks(&self, query: &str, limit: usize) -> Result<Vec<Track>> {
+        let query = query.to_lowercase();
+
+        Ok(self
+            .lock()
+            .library
+            .iter()
+            .filter(|t| {
+                [&t.name, &t.album, &t.artist]
+                    .iter()
+                    .any(|field| field.to_lowercase().contains(&query))
+            })
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
+    async fn search_playlists(&self, query: &str) -> Result<Vec<Playlist>> {
+        Ok(self
+            .lock()
+            .playlists
+            .iter()
+            .filter(|p| p.name.contains(query))
+            .cloned()
+            .collect())
+    }
+
+    async fn playlists(&self) -> Result<Vec<Playlist>> {
+        Ok(self.lock().playlists.clone())
+    }
+
+    async fn playlist_tracks(&self, name: &str) -> Result<Vec<Track>> {
+        let inner = self.lock();
+
+        if !inner.playlists.iter().any(|p| p.name == name) {
+            return Err(eyre!("No playlist named {name}"));
+        }
+
+        Ok(inner.playlist_tracks.get(name).cloned().unwrap_or_default())
+    }
+
+    async fn create_playlist(&self, name: &str) -> Result<()> {
+        self.record("create playlist", None);
+
+        self.lock().playlists.push(Playlist {
+            name: name.to_owned(),
+            duration: 0,
+            track_count: 0,
+        });
+        Ok(())
+    }
+
+    #[expect(clippy::cast_possible_truncation)]
+    async fn add_to_playlist(&self, name: &str, track_id: &str) -> Result<()> {
+        self.record("add to playlist", None);
+
+        let mut inner = self.lock();
+        let track = inner
+            .library
+            .iter()
+            .find(|t| t.id == track_id)
+            .cloned()
+            .ok_or_else(|| eyre!("No track with ID {track_id}"))?;
+        let playlist = inner
+            .playlists
+            .iter_mut()
+            .find(|p| p.name == name)
+            .ok_or_else(|| eyre!("No playlist named {name}"))?;
+
+        playlist.duration += track.duration as i32;
+        playlist.track_count += 1;
+
+        inner
+            .playlist_tracks
+            .entry(name.to_owned())
+            .or_default()
+            .push(track);
+        Ok(())
+    }
+
+    #[expect(clippy::cast_possible_truncation)]
+    async fn remove_from_playlist(&self, name: &str, track_id: &str) -> Result<()> {
+        self.record("remove from playlist", None);
+
+        let mut inner = self.lock();
+        let mut tracks = inner.playlist_tracks.remove(name).unwrap_or_default();
+        let playlist = inner
+            .playlists
+            .iter_mut()
+            .find(|p| p.name == name)
+            .ok_or_else(|| eyre!("No playlist named {name}"))?;
+
+        tracks.retain(|track| {
+            if track.id != track_id {
+                return true;
+            }
+
+            playlist.duration -= track.duration as i32;
+            playlist.track_count -= 1;
+            false
+        });
+
+        inner.playlist_tracks.insert(name.to_owned(), tracks);
+        Ok(())
+    }
+
+    async fn play_track(&self, id: &str) -> Result<()> {
+        self.record("play track", None);
+
+        let mut inner = self.lock();
+        let track = inner
+            .library
+            .iter()
+            .find(|t| t.id == id)
+            .cloned()
+            .ok_or_else(|| eyre!("No track with ID {id}"))?;
+
+        inner.current.state = PlayerState::Playing;
+        inner.current.position = Some(0.);
+        inner.current.track = Some(track);
+        Ok(())
+    }
+
+    async fn play_playlist(&self, name: &str) -> Result<()> {
+        self.record("play playlist", Some(PlayerState::Playing));
+
+        let mut inner = self.lock();
+        let playlist = inner
+            .playlists
+            .iter()
+            .find(|p| p.name == name)
+            .cloned()
+            .ok_or_else(|| eyre!("No playlist named {name}"))?;
+
+        inner.current.playlist = Some(playlist);
+        Ok(())
+    }
+
+    async fn play(&self) -> Result<()> {
+        self.record("play", Some(PlayerState::Playing));
+        Ok(())
+    }
+
+    async fn pause(&self) -> Result<()> {
+        self.record("pause", Some(PlayerState::Paused));
+        Ok(())
+    }
+
+    async fn back_track(&self) -> Result<()> {
+        self.record("back track", None);
+        self.advance();
+        Ok(())
+    }
+
+    async fn fast_forward(&self) -> Result<()> {
+        self.record("fast forward", Some(PlayerState::Forwarding));
+        Ok(())
+    }
+
+    async fn next_track(&self) -> Result<()> {
+        self.record("next track", None);
+        self.advance();
+        Ok(())
+    }
+
+    async fn previous_track(&self) -> Result<()> {
+        self.record("previous track", None);
+        self.advance();
+        Ok(())
+    }
+
+    async fn resume(&self) -> Result<()> {
+        self.record("resume", Some(PlayerState::Playing));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn playlist(name: &str) -> Playlist {
+        Playlist {
+            name: name.to_owned(),
+            duration: 0,
+            track_count: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn track_changes_follow_the_script() {
+        let backend = ScriptedBackend::new(Snapshot::playing(track("1", "One")).position(42.))
+            .then(Snapshot::playing(track("2", "Two")));
+        let clone = backend.clone();
+
+        assert_eq!(backend.player_position().await.unwrap(), Some(42.));
+
+        clone.next_track().await.unwrap();
+        assert_eq!(
+            backend.current_track_id().await.unwrap().as_deref(),
+            Some("2")
+        );
+        assert_eq!(backend.player_position().await.unwrap(), Some(0.));
+
+        // The script has run out, so the player stays where it is
+        assert!(!backend.advance());
+        assert_eq!(backend.commands(), ["next track"]);
+    }
+
+    #[tokio::test]
+    async fn transport_commands_change_the_state() {
+        let backend = ScriptedBackend::new(Snapshot::playing(track("1", "One")));
+
+        backend.pause().await.unwrap();
+        assert_eq!(backend.player_state().await.unwrap(), PlayerState::Paused);
+
+        backend.play().await.unwrap();
+        assert_eq!(backend.player_state().await.unwrap(), PlayerState::Playing);
+    }
+
+    #[tokio::test]
+    async fn nothing_is_current_while_stopped() {
+        let backend = ScriptedBackend::new(Snapshot::playing(track("1", "One")).lyrics("Hello"));
+        assert_eq!(
+            backend.current_track_lyrics().await.unwrap().as_deref(),
+            Some("Hello")
+        );
+
+        let backend = ScriptedBackend::new(
+            Snapshot::playing(track("1", "One"))
+                .lyrics("Hello")
+                .state(PlayerState::Stopped),
+        );
+        assert_eq!(backend.current_track_id().await.unwrap(), None);
+        assert_eq!(backend.current_track().await.unwrap(), None);
+        assert_eq!(backend.current_track_lyrics().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn plays_from_the_library() {
+        let backend = ScriptedBackend::new(Snapshot::stopped())
+            .library(vec![track("1", "One"), track("2", "Two")], Vec::new());
+
+        let found = backend.search_tracks("two", 10).await.unwrap();
+        assert_eq!(found, [track("2", "Two")]);
+
+        backend.play_track("2").await.unwrap();
+        assert_eq!(
+            backend.current_track().await.unwrap(),
+            Some(track("2", "Two"))
+        );
+        assert!(backend.play_track("3").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn upcoming_tracks_follow_the_current_track() {
+        let backend =
+            ScriptedBackend::new(Snapshot::playing(track("2", "Two")).playlist(playlist("Mix")))
+                .playlist_tracks(
+                    playlist("Mix"),
+                    vec![track("1", "One"), track("2", "Two"), track("3", "Three")],
+                );
+
+        assert_eq!(
+            backend.upcoming_tracks(10).await.unwrap(),
+            [track("3", "Three")]
+        );
+
+        backend.play_upcoming(1).await.unwrap();
+        assert_eq!(
+            backend.current_track_id().await.unwrap().as_deref(),
+            Some("3")
+        );
+        assert!(backend.upcoming_tracks(10).await.unwrap().is_empty());
+    }
+}
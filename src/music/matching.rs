@@ -0,0 +1,296 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Scoring catalog search results against a track in the library, so that remasters, live
+//! versions and the like aren't mistaken for the track.
+//!
+//! Everything here is a pure function of the track and the candidates, so that it can be
+//! checked against sample search results.
+
+use std::{collections::HashSet, sync::LazyLock};
+
+use regex::Regex;
+
+use super::Track;
+
+/// Seconds the durations may differ by and still be the same recording.
+const DURATION_TOLERANCE: f64 = 3.;
+/// Seconds the durations may differ by before they don't count towards a match at all.
+const DURATION_LIMIT: f64 = 30.;
+
+const TITLE_WEIGHT: f64 = 0.4;
+const ARTIST_WEIGHT: f64 = 0.25;
+const ALBUM_WEIGHT: f64 = 0.15;
+const DURATION_WEIGHT: f64 = 0.15;
+const EXPLICIT_WEIGHT: f64 = 0.05;
+
+/// Featured artists in a title, which the catalog and the library don't agree on listing.
+static FEATURING_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\s*[(\[](feat\.?|ft\.?|featuring|with)\s[^)\]]*[)\]]|\s+(feat\.?|ft\.?|featuring)\s.*$")
+        .unwrap()
+});
+
+/// What the catalog appends to the names of singles and EPs.
+static RELEASE_TYPE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\s+-\s+(single|ep)$").unwrap());
+
+/// Separators between the primary artist and the others. Only a lowercase "x" separates
+/// artists, so that names like "Lil Nas X" aren't split.
+static ARTIST_SEPARATOR_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i:,|&|\s(feat\.?|ft\.?|featuring)\s)|\sx\s").unwrap());
+
+/// A song from the catalog, as far as matching is concerned.
+#[derive(Debug, Clone)]
+pub struct Candidate<'a> {
+    pub name: &'a str,
+    pub artist: &'a str,
+    pub album: &'a str,
+    /// Duration in seconds
+    pub duration: Option<f64>,
+    /// Whether this is the explicit or the clean version, for songs that have both
+    pub explicit: Option<bool>,
+}
+
+/// Lowercase `s` and reduce it to words, dropping punctuation.
+pub fn normalize(s: &str) -> String {
+    s.replace('&', " and ")
+        .chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+        .collect::<String>()
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The title without featured artists, keeping descriptions like "Live" or "Remastered"
+/// that tell versions apart.
+fn title(name: &str) -> String {
+    normalize(&FEATURING_REGEX.replace_all(name, ""))
+}
+
+fn album(name: &str) -> String {
+    normalize(&RELEASE_TYPE_REGEX.replace(name, ""))
+}
+
+fn primary_artist(artist: &str) -> String {
+    normalize(
+        ARTIST_SEPARATOR_REGEX
+            .split(artist)
+            .next()
+            .unwrap_or(artist),
+    )
+}
+
+/// How many of the words in `a` and `b` are shared, from 0 to 1.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    if a == b {
+        return 1.;
+    }
+
+    let a = a.split_whitespace().collect::<HashSet<_>>();
+    let b = b.split_whitespace().collect::<HashSet<_>>();
+
+    if a.is_empty() || b.is_empty() {
+        return 0.;
+    }
+
+    #[expect(clippy::cast_precision_loss)]
+    let score = 2. * a.intersection(&b).count() as f64 / (a.len() + b.len()) as f64;
+    score
+}
+
+/// How well the durations agree, or a neutral score if the catalog doesn't say.
+fn duration_score(track: f64, candidate: Option<f64>) -> f64 {
+    let Some(candidate) = candidate else {
+        return 0.5;
+    };
+
+    let difference = (track - candidate).abs();
+    1. - ((difference - DURATION_TOLERANCE) / (DURATION_LIMIT - DURATION_TOLERANCE)).clamp(0., 1.)
+}
+
+/// Music doesn't tell whether a track is explicit, so prefer the explicit version of songs
+/// that have both, unless the track says it is the clean one.
+fn explicit_score(track: &Track, candidate: &Candidate) -> f64 {
+    let Some(explicit) = candidate.explicit else {
+        return 1.;
+    };
+
+    let clean = normalize(&track.name)
+        .split_whitespace()
+        .chain(normalize(&track.album).split_whitespace())
+        .any(|word| word == "clean");
+
+    if explicit == clean { 0. } else { 1. }
+}
+
+/// How confident we can be that `candidate` is `track`, from 0 to 1.
+pub fn score(track: &Track, candidate: &Candidate) -> f64 {
+    let title = similarity(&title(&track.name), &title(candidate.name));
+
+    let artist = similarity(&normalize(&track.artist), &normalize(candidate.artist)).max(
+        if primary_artist(&track.artist) == primary_artist(candidate.artist) {
+            0.9
+        } else {
+            0.
+        },
+    );
+
+    let album = similarity(&album(&track.album), &album(candidate.album));
+
+    TITLE_WEIGHT * title
+        + ARTIST_WEIGHT * artist
+        + ALBUM_WEIGHT * album
+        + DURATION_WEIGHT * duration_score(track.duration, candidate.duration)
+        + EXPLICIT_WEIGHT * explicit_score(track, candidate)
+}
+
+/// The candidate most likely to be `track` along with the confidence in it, preferring the
+/// earliest one, which the catalog ranks highest, on ties.
+pub fn best_match<'a, T>(
+    track: &Track,
+    candidates: impl IntoIterator<Item = (T, Candidate<'a>)>,
+) -> Option<(T, f64)> {
+    candidates
+        .into_iter()
+        .map(|(item, candidate)| (item, score(track, &candidate)))
+        .fold(None, |best, (item, confidence)| match best {
+            Some((_, best_confidence)) if best_confidence >= confidence => best,
+            _ => Some((item, confidence)),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(name: &str, album: &str, artist: &str) -> Track {
+        Track {
+            id: "0".into(),
+            name: name.into(),
+            album: album.into(),
+            artist: artist.into(),
+            duration: 200.,
+            location: None,
+            favorited: false,
+            disliked: false,
+            rating: 0,
+        }
+    }
+
+    fn candidate<'a>(name: &'a str, album: &'a str, artist: &'a str) -> Candidate<'a> {
+        Candidate {
+            name,
+            artist,
+            album,
+            duration: Some(200.),
+            explicit: None,
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn normalizes_punctuation_and_case() {
+        assert_eq!(normalize("  Don't Stop Me Now!  "), "dont stop me now");
+        assert_eq!(normalize("Simon & Garfunkel"), "simon and garfunkel");
+        assert_eq!(normalize("Beyoncé"), "beyoncé");
+    }
+
+    #[test]
+    fn strips_featured_artists_from_titles() {
+        assert_eq!(
+            title("Old Town Road (feat. Billy Ray Cyrus)"),
+            "old town road"
+        );
+        assert_eq!(title("Stay [with Justin Bieber]"), "stay");
+        assert_eq!(title("Airplanes ft. Hayley Williams"), "airplanes");
+        // Only featured artists, not other descriptions
+        assert_eq!(title("Hotel California (Live)"), "hotel california live");
+    }
+
+    #[test]
+    fn strips_release_types_from_albums() {
+        assert_eq!(album("Old Town Road - Single"), "old town road");
+        assert_eq!(album("7 - EP"), "7");
+        assert_eq!(album("Greatest Hits - Live"), "greatest hits live");
+    }
+
+    #[test]
+    fn splits_off_the_primary_artist() {
+        for artist in [
+            "Lil Nas X & Billy Ray Cyrus",
+            "Lil Nas X, Billy Ray Cyrus",
+            "Lil Nas X feat. Billy Ray Cyrus",
+            "Lil Nas X x Billy Ray Cyrus",
+        ] {
+            assert_eq!(primary_artist(artist), "lil nas x", "{artist}");
+        }
+    }
+
+    #[test]
+    fn measures_shared_words() {
+        assert_close(similarity("hotel california", "hotel california"), 1.);
+        assert_close(similarity("hotel california", "hotel california live"), 0.8);
+        assert_close(similarity("hotel california", "bohemian rhapsody"), 0.);
+        assert_close(similarity("", "bohemian rhapsody"), 0.);
+    }
+
+    #[test]
+    fn scores_equivalent_names_fully() {
+        let track = track(
+            "Old Town Road (feat. Billy Ray Cyrus)",
+            "Old Town Road - Single",
+            "Lil Nas X & Billy Ray Cyrus",
+        );
+
+        assert_close(
+            score(
+                &track,
+                &candidate(
+                    "Old Town Road",
+                    "Old Town Road",
+                    "Lil Nas X & Billy Ray Cyrus",
+                ),
+            ),
+            1.,
+        );
+
+        // Crediting only the primary artist costs a little
+        assert_close(
+            score(
+                &track,
+                &candidate("Old Town Road", "Old Town Road", "Lil Nas X"),
+            ),
+            1. - ARTIST_WEIGHT * 0.1,
+        );
+    }
+
+    #[test]
+    fn prefers_the_explicit_version_unless_the_track_is_clean() {
+        let explicit = Candidate {
+            explicit: Some(true),
+            ..candidate("Humble", "Damn", "Kendrick Lamar")
+        };
+        let clean = Candidate {
+            explicit: Some(false),
+            ..candidate("Humble", "Damn", "Kendrick Lamar")
+        };
+
+        let dirty = track("Humble", "Damn", "Kendrick Lamar");
+        assert_close(score(&dirty, &explicit), 1.);
+        assert_close(score(&dirty, &clean), 1. - EXPLICIT_WEIGHT);
+
+        // The album names now only share one of their words
+        let edited = track("Humble", "Damn (Clean)", "Kendrick Lamar");
+        assert_close(score(&edited, &clean), 1. - ALBUM_WEIGHT / 3.);
+        assert_close(
+            score(&edited, &explicit),
+            1. - ALBUM_WEIGHT / 3. - EXPLICIT_WEIGHT,
+        );
+    }
+}
@@ -2,15 +2,35 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use std::sync::{LazyLock, OnceLock};
+use std::{
+    path::PathBuf,
+    sync::{LazyLock, Mutex},
+};
+use tokio::fs;
 
+use base64::{Engine as _, prelude::BASE64_URL_SAFE_NO_PAD};
+use chrono::Utc;
 use eyre::{Result, eyre};
 use regex::Regex;
+use reqwest::{StatusCode, Url};
+use serde::{Deserialize, Serialize};
 
-use super::{Track, models::AppleMusicData};
-use crate::http::HTTP;
+use super::{
+    Track,
+    cache::Cache,
+    matching::{self, Candidate},
+    models::{AppleMusicData, AppleMusicDataResultsSongData},
+};
+use crate::{config::MetadataConfig, dirs, http::HTTP};
 
-static TOKEN_CACHE: OnceLock<String> = OnceLock::new();
+static TOKEN_CACHE: Mutex<Option<Token>> = Mutex::new(None);
+
+/// How many songs to consider when searching the catalog.
+const SEARCH_LIMIT: usize = 10;
+
+/// Seconds before it expires that a token is replaced, so that it doesn't expire between
+/// being loaded and being used.
+const TOKEN_EXPIRY_MARGIN: i64 = 60;
 
 static USER_AGENT: &str = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/128.0.0.0 Safari/537.36";
 
@@ -25,19 +45,94 @@ static TOKEN_REGEX: LazyLock<Regex> = LazyLock::new(|| {
     .unwrap()
 });
 
-#[derive(Debug)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Metadata {
     pub album_artwork: String,
     pub artist_artwork: Option<String>,
     pub share_url: String,
     pub song_link: String,
+    /// How confident the match in the catalog is, from 0 to 1
+    #[serde(default)]
+    pub confidence: f64,
+}
+
+/// An Apple Music API token, cached in `$XDG_CACHE_HOME/am/token.json` until it expires.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Token {
+    token: String,
+    /// The `exp` claim of the token as a Unix timestamp, if it has one
+    expires_at: Option<i64>,
+}
+
+impl Token {
+    fn new(token: &str) -> Self {
+        Self {
+            token: token.to_owned(),
+            expires_at: expiry(token),
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        self.expires_at
+            .is_none_or(|expires_at| expires_at - TOKEN_EXPIRY_MARGIN > Utc::now().timestamp())
+    }
+
+    fn path() -> Result<PathBuf> {
+        Ok(dirs::cache_dir()?.join("token.json"))
+    }
+
+    async fn load() -> Option<Self> {
+        let data = fs::read(Self::path().ok()?).await.ok()?;
+        serde_json::from_slice(&data).ok()
+    }
+
+    async fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        fs::write(path, serde_json::to_vec(self)?).await?;
+
+        Ok(())
+    }
+}
+
+/// Decode the `exp` claim of a JWT, without verifying it.
+fn expiry(token: &str) -> Option<i64> {
+    #[derive(Deserialize)]
+    struct Claims {
+        exp: i64,
+    }
+
+    let payload = token.split('.').nth(1)?;
+    let payload = BASE64_URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .ok()?;
+
+    serde_json::from_slice::<Claims>(&payload)
+        .ok()
+        .map(|claims| claims.exp)
 }
 
+/// A token for the Apple Music API, reused from the cache until it expires.
 pub async fn fetch_token() -> Result<String> {
-    if let Some(token) = TOKEN_CACHE.get() {
-        return Ok(token.to_owned());
+    let cached = TOKEN_CACHE.lock().unwrap().clone();
+    if let Some(token) = cached.filter(Token::is_valid) {
+        return Ok(token.token);
+    }
+
+    if let Some(token) = Token::load().await.filter(Token::is_valid) {
+        *TOKEN_CACHE.lock().unwrap() = Some(token.clone());
+        return Ok(token.token);
     }
 
+    refresh_token().await
+}
+
+/// Obtain a new token from the web player, replacing the cached one.
+async fn refresh_token() -> Result<String> {
     let html = HTTP
         .get("https://music.apple.com/")
         .send()
@@ -50,7 +145,7 @@ pub async fn fetch_token() -> Result<String> {
         .captures(&html)
         .ok_or_else(|| eyre!("could not obtain bundle for API token"))?[1];
 
-    let mut bundle_url = "https://music.apple.com/".parse::<reqwest::Url>()?;
+    let mut bundle_url = "https://music.apple.com/".parse::<Url>()?;
     bundle_url.set_path(bundle_path);
 
     let bundle = HTTP
@@ -61,50 +156,122 @@ pub async fn fetch_token() -> Result<String> {
         .text()
         .await?;
 
-    let token = &TOKEN_REGEX
-        .captures(&bundle)
-        .ok_or_else(|| eyre!("could not find API token in bundle"))?[1];
+    let token = Token::new(
+        &TOKEN_REGEX
+            .captures(&bundle)
+            .ok_or_else(|| eyre!("could not find API token in bundle"))?[1],
+    );
+
+    // A token that couldn't be cached can still be used
+    let _ = token.save().await;
+    *TOKEN_CACHE.lock().unwrap() = Some(token.clone());
+
+    Ok(token.token)
+}
+
+/// Metadata for `track`, from the cache if it was looked up before.
+pub async fn fetch_metadata(track: &Track, config: &MetadataConfig) -> Result<Metadata> {
+    let metadata = if config.cache_size == 0 {
+        search_metadata(track, config).await?
+    } else {
+        // A broken cache shouldn't get in the way of finding metadata
+        let mut cache = Cache::load().await.unwrap_or_default();
+
+        let metadata = if let Some(entry) = cache.get(track, config) {
+            entry.metadata.clone()
+        } else {
+            let metadata = search_metadata(track, config).await?;
+            cache.insert(track, metadata.clone(), config);
+            metadata
+        };
 
-    TOKEN_CACHE.set(token.to_owned()).unwrap();
+        let _ = cache.save().await;
+        metadata
+    };
 
-    Ok(token.to_owned())
+    metadata.ok_or_else(|| eyre!("could not find track metadata"))
 }
 
-pub async fn fetch_metadata(track: &Track) -> Result<Metadata> {
+#[expect(clippy::cast_precision_loss)]
+fn candidate(song: &AppleMusicDataResultsSongData) -> Candidate<'_> {
+    let attributes = &song.attributes;
+
+    Candidate {
+        name: &attributes.name,
+        artist: &attributes.artist_name,
+        album: &attributes.album_name,
+        duration: attributes
+            .duration_in_millis
+            .map(|millis| millis as f64 / 1000.),
+        explicit: match attributes.content_rating.as_deref() {
+            Some("explicit") => Some(true),
+            Some("clean") => Some(false),
+            _ => None,
+        },
+    }
+}
+
+/// The song in the search results that is most likely `track` along with the confidence in
+/// it, if it is confident enough.
+fn best_song<'a>(
+    data: &'a AppleMusicData,
+    track: &Track,
+    config: &MetadataConfig,
+) -> Option<(&'a AppleMusicDataResultsSongData, f64)> {
+    let candidates = data
+        .results
+        .song
+        .data
+        .iter()
+        .map(|song| (song, candidate(song)));
+
+    matching::best_match(track, candidates)
+        .filter(|(_, confidence)| *confidence >= config.min_confidence)
+}
+
+/// Search the catalog for `track`, returning `None` if there is no confident match.
+async fn search_metadata(track: &Track, config: &MetadataConfig) -> Result<Option<Metadata>> {
     let token = fetch_token().await?;
     let song_key = track.name.clone() + " " + &track.album + " " + &track.artist;
 
-    let mut api_url =
-        "https://amp-api-edge.music.apple.com/v1/catalog/us/search".parse::<reqwest::Url>()?;
+    let mut api_url = "https://amp-api-edge.music.apple.com/v1/catalog/"
+        .parse::<Url>()?
+        .join(&format!("{}/search", config.storefront))?;
     api_url
         .query_pairs_mut()
         .append_pair("platform", "web")
         .append_pair("l", "en-US")
-        .append_pair("limit", "1")
+        .append_pair("limit", &SEARCH_LIMIT.to_string())
         .append_pair("with", "serverBubbles")
         .append_pair("types", "songs")
         .append_pair("term", &song_key)
         .append_pair("include[songs]", "artists");
 
-    let data: AppleMusicData = HTTP
-        .get(api_url)
-        .bearer_auth(&token)
-        .header("accept", "*/*")
-        .header("accept-language", "en-US,en;q=0.9")
-        .header("user-agent", USER_AGENT)
-        .header("origin", "https://music.apple.com")
-        .send()
-        .await?
-        .error_for_status()?
-        .json()
-        .await?;
+    let search = |token: String| {
+        HTTP.get(api_url.clone())
+            .bearer_auth(token)
+            .header("accept", "*/*")
+            .header("accept-language", "en-US,en;q=0.9")
+            .header("user-agent", USER_AGENT)
+            .header("origin", "https://music.apple.com")
+            .send()
+    };
 
-    let track_data = data
-        .results
-        .song
-        .data
-        .first()
-        .ok_or_else(|| eyre!("could not find track metadata"))?;
+    let mut response = search(token).await?;
+
+    // The token may have been revoked before it expired
+    if matches!(
+        response.status(),
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN
+    ) {
+        response = search(refresh_token().await?).await?;
+    }
+
+    let data: AppleMusicData = response.error_for_status()?.json().await?;
+
+    let Some((track_data, confidence)) = best_song(&data, track, config) else {
+        return Ok(None);
+    };
 
     let album_artwork = track_data
         .attributes
@@ -119,10 +286,142 @@ pub async fn fetch_metadata(track: &Track) -> Result<Metadata> {
         .first()
         .map(|data| data.attributes.artwork.url.replace("{w}x{h}", "512x512"));
 
-    Ok(Metadata {
+    Ok(Some(Metadata {
         album_artwork,
         artist_artwork,
         share_url: track_data.attributes.url.clone(),
         song_link: format!("https://song.link/i/{}", track_data.id),
-    })
+        confidence,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(name: &str, album: &str, artist: &str, duration: f64) -> Track {
+        Track {
+            id: "0".into(),
+            name: name.into(),
+            album: album.into(),
+            artist: artist.into(),
+            duration,
+            location: None,
+            favorited: false,
+            disliked: false,
+            rating: 0,
+        }
+    }
+
+    /// The fixtures are hand-written in the shape of catalog search responses, with the
+    /// kinds of near misses that real searches turn up.
+    #[test]
+    fn matches_sample_searches() {
+        const BOHEMIAN_RHAPSODY: &str = include_str!("fixtures/search-bohemian-rhapsody.json");
+        const HOTEL_CALIFORNIA: &str = include_str!("fixtures/search-hotel-california.json");
+        const OLD_TOWN_ROAD: &str = include_str!("fixtures/search-old-town-road.json");
+        const BLUE_MONDAY: &str = include_str!("fixtures/search-blue-monday.json");
+        const HUMBLE: &str = include_str!("fixtures/search-humble.json");
+        const NO_RESULTS: &str = include_str!("fixtures/search-no-results.json");
+
+        let cases = [
+            // The original rather than the remaster listed before it, and the other way around
+            (
+                BOHEMIAN_RHAPSODY,
+                track("Bohemian Rhapsody", "A Night at the Opera", "Queen", 354.3),
+                Some("1003"),
+            ),
+            (
+                BOHEMIAN_RHAPSODY,
+                track(
+                    "Bohemian Rhapsody (Remastered 2011)",
+                    "A Night at the Opera (2011 Remaster)",
+                    "Queen",
+                    354.9,
+                ),
+                Some("1001"),
+            ),
+            // The studio recording rather than the live one listed before it, and the live one
+            // when that is the track
+            (
+                HOTEL_CALIFORNIA,
+                track("Hotel California", "Hotel California", "Eagles", 391.4),
+                Some("2002"),
+            ),
+            (
+                HOTEL_CALIFORNIA,
+                track(
+                    "Hotel California (Live on MTV, 1994)",
+                    "Hell Freezes Over",
+                    "Eagles",
+                    434.2,
+                ),
+                Some("2001"),
+            ),
+            // A cover by another artist isn't the song
+            (
+                HOTEL_CALIFORNIA,
+                track("Hotel California", "Covers", "The Cover Band", 300.),
+                None,
+            ),
+            // Featured artists in the title, which the library lists differently
+            (
+                OLD_TOWN_ROAD,
+                track(
+                    "Old Town Road (Remix) [feat. Billy Ray Cyrus]",
+                    "Old Town Road (Remix) - Single",
+                    "Lil Nas X & Billy Ray Cyrus",
+                    157.1,
+                ),
+                Some("3001"),
+            ),
+            (
+                OLD_TOWN_ROAD,
+                track("Old Town Road", "7 - EP", "Lil Nas X", 113.),
+                Some("3002"),
+            ),
+            // The same title with a duration far off loses to a similar title that fits
+            (
+                BLUE_MONDAY,
+                track("Blue Monday", "Singles", "New Order", 448.7),
+                Some("4001"),
+            ),
+            (
+                BLUE_MONDAY,
+                track("Blue Monday", "Singles", "New Order", 244.6),
+                Some("4002"),
+            ),
+            // The explicit version over the clean one listed before it, unless the track
+            // says it is clean
+            (
+                HUMBLE,
+                track("HUMBLE.", "DAMN.", "Kendrick Lamar", 177.),
+                Some("5002"),
+            ),
+            (
+                HUMBLE,
+                track("HUMBLE.", "DAMN. (Clean)", "Kendrick Lamar", 177.),
+                Some("5001"),
+            ),
+            (
+                BOHEMIAN_RHAPSODY,
+                track("Untitled Demo", "Voice Memos", "Me", 95.),
+                None,
+            ),
+            (
+                NO_RESULTS,
+                track("Bohemian Rhapsody", "A Night at the Opera", "Queen", 354.3),
+                None,
+            ),
+        ];
+
+        let config = MetadataConfig::default();
+
+        for (fixture, track, expected) in cases {
+            let data = serde_json::from_str::<AppleMusicData>(fixture).unwrap();
+            let id = best_song(&data, &track, &config).map(|(song, _)| song.id.as_str());
+
+            assert_eq!(id, expected, "{} by {}", track.name, track.artist);
+        }
+    }
 }
@@ -2,39 +2,47 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use std::process::Stdio;
+use std::path::PathBuf;
 use tokio::process::Command;
 
 use eyre::{Result, bail, eyre};
 
+mod applescript;
+mod backend;
+pub mod cache;
+#[cfg(test)]
+pub mod fake;
+pub mod matching;
 mod metadata;
 mod models;
 
+pub use applescript::AppleScript;
+pub use backend::PlayerBackend;
 pub use metadata::*;
 
-pub async fn is_running() -> Result<bool> {
-    Ok(Command::new("pgrep")
-        .arg(r"^Music$")
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .status()
-        .await?
-        .success())
-}
-
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Track {
+    /// Database ID
     pub id: String,
     pub name: String,
     pub album: String,
     pub artist: String,
+    /// Duration in seconds
     pub duration: f64,
+    /// Path to the audio file, for tracks stored locally
+    pub location: Option<PathBuf>,
+    pub favorited: bool,
+    pub disliked: bool,
+    /// Star rating from 0 to 5
+    pub rating: u8,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Playlist {
     pub name: String,
+    /// Total duration in seconds
     pub duration: i32,
+    pub track_count: usize,
 }
 
 pub async fn tell_raw(applescript: &[&str]) -> Result<String> {
@@ -59,7 +67,13 @@ pub async fn tell(applescript: &str) -> Result<String> {
     tell_raw(&[r#"tell application "Music""#, applescript, r"end tell"]).await
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+/// Quote a string as a string literal for use in scripts.
+pub fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum PlayerState {
     Stopped,
     Playing,
@@ -115,61 +129,93 @@ impl std::str::FromStr for PlayerState {
     }
 }
 
-pub async fn get_player_state() -> Result<PlayerState> {
-    tell("get player state").await?.parse::<PlayerState>()
-}
-
-pub async fn get_current_track() -> Result<Option<Track>> {
-    let player_state = get_player_state().await?;
-
-    if player_state == PlayerState::Stopped {
-        Ok(None)
-    } else {
-        let track_data = tell_raw(&[
-            r#"set output to """#,
-            r#"tell application "Music""#,
-            r"set t_id to database id of current track",
-            r"set t_name to name of current track",
-            r"set t_album to album of current track",
-            r"set t_artist to artist of current track",
-            r"set t_duration to duration of current track",
-            r#"set output to "" & t_id & "\n" & t_name & "\n" & t_album & "\n" & t_artist & "\n" & t_duration"#,
-            r"end tell",
-            r"return output"
-        ])
-        .await?;
-
-        let mut track_data = track_data.split('\n');
-
-        let id = track_data
-            .next()
-            .ok_or_else(|| eyre!("Could not obtain track ID"))?
-            .to_owned();
-        let name = track_data
-            .next()
-            .ok_or_else(|| eyre!("Could not obtain track name"))?
-            .to_owned();
-        let album = track_data
-            .next()
-            .ok_or_else(|| eyre!("Could not obtain track album"))?
-            .to_owned();
-        let artist = track_data
-            .next()
-            .ok_or_else(|| eyre!("Could not obtain track artist"))?
-            .to_owned();
-        let duration = track_data
-            .next()
-            .ok_or_else(|| eyre!("Could not obtain track duration"))?
-            .to_owned()
-            .replace(',', ".")
-            .parse::<f64>()?;
-
-        Ok(Some(Track {
-            id,
-            name,
-            album,
-            artist,
-            duration,
-        }))
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShuffleMode {
+    Songs,
+    Albums,
+    Groupings,
+}
+
+impl std::fmt::Display for ShuffleMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Songs => "songs",
+                Self::Albums => "albums",
+                Self::Groupings => "groupings",
+            }
+        )
+    }
+}
+
+impl std::str::FromStr for ShuffleMode {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "songs" => Ok(Self::Songs),
+            "albums" => Ok(Self::Albums),
+            "groupings" => Ok(Self::Groupings),
+            _ => Err(eyre!("Unknown shuffle mode {s:?}")),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RepeatMode {
+    Off,
+    One,
+    All,
+}
+
+impl RepeatMode {
+    pub fn to_icon(self) -> String {
+        match self {
+            Self::Off => "󰑗",
+            Self::One => "󰑘",
+            Self::All => "󰑖",
+        }
+        .into()
+    }
+
+    /// The mode after this one when cycling through modes.
+    #[must_use]
+    pub fn next(self) -> Self {
+        match self {
+            Self::Off => Self::All,
+            Self::All => Self::One,
+            Self::One => Self::Off,
+        }
+    }
+}
+
+impl std::fmt::Display for RepeatMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Off => "off",
+                Self::One => "one",
+                Self::All => "all",
+            }
+        )
+    }
+}
+
+impl std::str::FromStr for RepeatMode {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "off" => Ok(Self::Off),
+            "one" => Ok(Self::One),
+            "all" => Ok(Self::All),
+            _ => Err(eyre!("Unknown repeat mode {s:?}")),
+        }
     }
 }
@@ -9,10 +9,12 @@ pub struct AppleMusicData {
 
 #[derive(serde::Deserialize)]
 pub struct AppleMusicDataResults {
+    /// Missing if nothing was found
+    #[serde(default)]
     pub song: AppleMusicDataResultsSong,
 }
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, Default)]
 pub struct AppleMusicDataResultsSong {
     pub data: Vec<AppleMusicDataResultsSongData>,
 }
@@ -25,9 +27,17 @@ pub struct AppleMusicDataResultsSongData {
 }
 
 #[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct AppleMusicDataResultsSongDataAttributes {
     pub url: String,
     pub artwork: AppleMusicDataResultsSongDataAttributesArtwork,
+    pub name: String,
+    pub artist_name: String,
+    #[serde(default)]
+    pub album_name: String,
+    pub duration_in_millis: Option<u64>,
+    /// `explicit` or `clean`, missing for songs that are neither
+    pub content_rating: Option<String>,
 }
 
 #[derive(serde::Deserialize)]
@@ -0,0 +1,292 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The Last.fm scrobbling API.
+
+use async_trait::async_trait;
+use eyre::{Result, bail, eyre};
+use reqwest::Url;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use super::{Scrobble, Service};
+use crate::{config::LastfmConfig, http, music::Track, state::State};
+
+/// Last.fm accepts at most this many scrobbles per request.
+const BATCH_SIZE: usize = 50;
+
+/// An authorized Last.fm session, which doesn't expire.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    /// Name of the Last.fm user
+    pub name: String,
+    pub key: String,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Response<T> {
+    Error { error: u32, message: String },
+    Ok(T),
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    token: String,
+}
+
+#[derive(Deserialize)]
+struct SessionResponse {
+    session: Session,
+}
+
+#[derive(Debug)]
+pub struct LastFm {
+    config: LastfmConfig,
+    session: Option<Session>,
+}
+
+impl LastFm {
+    pub fn new(config: &LastfmConfig) -> Result<Self> {
+        if config.api_key.is_empty() || config.api_secret.is_empty() {
+            bail!("Set `lastfm.api_key` and `lastfm.api_secret` in the configuration file");
+        }
+
+        Ok(Self {
+            config: config.clone(),
+            session: None,
+        })
+    }
+
+    /// Use the session saved by `am scrobble auth`.
+    pub async fn authenticated(config: &LastfmConfig) -> Result<Self> {
+        let session = State::load()
+            .await?
+            .lastfm_session
+            .ok_or_else(|| eyre!("Not logged in to Last.fm, run `am scrobble auth` first"))?;
+
+        Ok(Self {
+            session: Some(session),
+            ..Self::new(config)?
+        })
+    }
+
+    /// Sign `params` as described in <https://www.last.fm/api/authspec#_8-signing-calls>.
+    fn sign(&self, params: &mut Vec<(String, String)>) {
+        params.sort();
+
+        let mut payload = String::new();
+        for (key, value) in params.iter() {
+            payload.push_str(key);
+            payload.push_str(value);
+        }
+        payload.push_str(&self.config.api_secret);
+
+        params.push((
+            "api_sig".into(),
+            format!("{:x}", md5::compute(payload.as_bytes())),
+        ));
+    }
+
+    async fn call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        mut params: Vec<(String, String)>,
+    ) -> Result<T> {
+        params.push(("method".into(), method.into()));
+        params.push(("api_key".into(), self.config.api_key.clone()));
+
+        if let Some(session) = &self.session {
+            params.push(("sk".into(), session.key.clone()));
+        }
+
+        self.sign(&mut params);
+        params.push(("format".into(), "json".into()));
+
+        let url = self.config.api_url.parse::<Url>()?;
+
+        let response = http::client_for(&url)
+            .post(url)
+            .form(&params)
+            .send()
+            .await?
+            .json::<Response<T>>()
+            .await?;
+
+        match response {
+            Response::Ok(data) => Ok(data),
+            Response::Error { error, message } => {
+                bail!("Last.fm {method} failed: {message} (error {error})")
+            }
+        }
+    }
+
+    /// Start the desktop authorization flow, returning a token and the URL where the user
+    /// has to authorize it.
+    pub async fn request_token(&self) -> Result<(String, Url)> {
+        let TokenResponse { token } = self.call("auth.getToken", Vec::new()).await?;
+
+        let mut url = self.config.auth_url.parse::<Url>()?;
+        url.query_pairs_mut()
+            .append_pair("api_key", &self.config.api_key)
+            .append_pair("token", &token);
+
+        Ok((token, url))
+    }
+
+    /// Exchange an authorized token for a session.
+    pub async fn create_session(&self, token: &str) -> Result<Session> {
+        let SessionResponse { session } = self
+            .call("auth.getSession", vec![("token".into(), token.into())])
+            .await?;
+
+        Ok(session)
+    }
+}
+
+#[async_trait]
+impl Service for LastFm {
+    fn id(&self) -> &'static str {
+        "lastfm"
+    }
+
+    fn name(&self) -> &'static str {
+        "Last.fm"
+    }
+
+    fn batch_size(&self) -> usize {
+        BATCH_SIZE
+    }
+
+    #[expect(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    async fn now_playing(&self, track: &Track) -> Result<()> {
+        self.call::<serde_json::Value>(
+            "track.updateNowPlaying",
+            vec![
+                ("artist".into(), track.artist.clone()),
+                ("track".into(), track.name.clone()),
+                ("album".into(), track.album.clone()),
+                ("duration".into(), (track.duration as u32).to_string()),
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn submit(&self, scrobbles: &[Scrobble]) -> Result<()> {
+        let params = scrobbles
+            .iter()
+            .enumerate()
+            .flat_map(|(index, scrobble)| {
+                [
+                    ("artist", scrobble.artist.clone()),
+                    ("track", scrobble.track.clone()),
+                    ("album", scrobble.album.clone()),
+                    ("duration", scrobble.duration.to_string()),
+                    ("timestamp", scrobble.timestamp.to_string()),
+                ]
+                .map(|(key, value)| (format!("{key}[{index}]"), value))
+            })
+            .collect();
+
+        self.call::<serde_json::Value>("track.scrobble", params)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::scrobble::{Scrobbler, stub::Stub, tests::queue_path};
+
+    fn config(api_url: &str) -> LastfmConfig {
+        LastfmConfig {
+            api_key: "key".into(),
+            api_secret: "secret".into(),
+            api_url: api_url.into(),
+            ..LastfmConfig::default()
+        }
+    }
+
+    #[test]
+    fn signs_sorted_params() {
+        let lastfm = LastFm::new(&config("")).unwrap();
+        let mut params = vec![
+            ("track[0]".to_owned(), "Song".to_owned()),
+            ("method".to_owned(), "track.scrobble".to_owned()),
+            ("api_key".to_owned(), "key".to_owned()),
+        ];
+
+        lastfm.sign(&mut params);
+
+        // md5("api_keykeymethodtrack.scrobbletrack[0]Songsecret")
+        assert_eq!(
+            params,
+            [
+                ("api_key".to_owned(), "key".to_owned()),
+                ("method".to_owned(), "track.scrobble".to_owned()),
+                ("track[0]".to_owned(), "Song".to_owned()),
+                (
+                    "api_sig".to_owned(),
+                    "4f92468b261e1d493e759c15721a433d".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn submits_in_batches_of_fifty() {
+        let stub = Stub::start(&[(200, "{}")]).await;
+        let queue = queue_path();
+
+        let scrobbles = (0..120)
+            .map(|index| Scrobble {
+                artist: "Artist".into(),
+                track: format!("Track {index}"),
+                album: "Album".into(),
+                duration: 180,
+                timestamp: 1_700_000_000 + index,
+            })
+            .collect::<Vec<_>>();
+        std::fs::write(&queue, serde_json::to_vec(&scrobbles).unwrap()).unwrap();
+
+        let lastfm = LastFm::new(&config(&stub.url)).unwrap();
+        Scrobbler::with_queue(lastfm, Duration::from_secs(1), queue.clone())
+            .flush()
+            .await
+            .unwrap();
+
+        let requests = stub.requests();
+        let sizes = requests
+            .iter()
+            .map(|request| {
+                assert_eq!(request.path, "/");
+                assert_eq!(
+                    request.header("content-type"),
+                    Some("application/x-www-form-urlencoded")
+                );
+
+                let form = request.form();
+                assert!(form.contains(&("method".into(), "track.scrobble".into())));
+                assert!(form.iter().any(|(key, _)| key == "api_sig"));
+
+                form.iter()
+                    .filter(|(key, _)| key.starts_with("track["))
+                    .count()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(sizes, [50, 50, 20]);
+
+        let last = requests[2].form();
+        assert!(last.contains(&("track[19]".into(), "Track 119".into())));
+        assert!(last.contains(&("timestamp[19]".into(), "1700000119".into())));
+
+        // Everything was submitted, so nothing is left queued
+        assert!(!queue.exists());
+    }
+}
@@ -0,0 +1,346 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The ListenBrainz `submit-listens` API.
+
+use async_trait::async_trait;
+use eyre::{Result, bail};
+use reqwest::Url;
+use serde::Serialize;
+
+use super::{Scrobble, Service};
+use crate::{config::ListenBrainzConfig, http, music::Track};
+
+/// How many listens to import at once after being offline, well below the API's limit.
+const BATCH_SIZE: usize = 100;
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ListenType {
+    PlayingNow,
+    Single,
+    Import,
+}
+
+#[derive(Serialize)]
+struct Submission<'a> {
+    listen_type: ListenType,
+    payload: Vec<Listen<'a>>,
+}
+
+#[derive(Serialize)]
+struct Listen<'a> {
+    /// When the listen started, as a Unix timestamp, left out for `playing_now`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    listened_at: Option<i64>,
+    track_metadata: TrackMetadata<'a>,
+}
+
+#[derive(Serialize)]
+struct TrackMetadata<'a> {
+    artist_name: &'a str,
+    track_name: &'a str,
+    release_name: &'a str,
+    additional_info: AdditionalInfo,
+}
+
+#[derive(Serialize)]
+struct AdditionalInfo {
+    duration_ms: u64,
+    submission_client: &'static str,
+    submission_client_version: &'static str,
+}
+
+impl AdditionalInfo {
+    fn new(duration: u32) -> Self {
+        Self {
+            duration_ms: u64::from(duration) * 1000,
+            submission_client: env!("CARGO_PKG_NAME"),
+            submission_client_version: env!("CARGO_PKG_VERSION"),
+        }
+    }
+}
+
+impl<'a> Listen<'a> {
+    fn new(scrobble: &'a Scrobble, listened_at: Option<i64>) -> Self {
+        Self {
+            listened_at,
+            track_metadata: TrackMetadata {
+                artist_name: &scrobble.artist,
+                track_name: &scrobble.track,
+                release_name: &scrobble.album,
+                additional_info: AdditionalInfo::new(scrobble.duration),
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ListenBrainz {
+    config: ListenBrainzConfig,
+}
+
+impl ListenBrainz {
+    pub fn new(config: &ListenBrainzConfig) -> Result<Self> {
+        if config.token.is_empty() {
+            bail!("Set `listenbrainz.token` in the configuration file");
+        }
+
+        Ok(Self {
+            config: config.clone(),
+        })
+    }
+
+    async fn submit_listens(&self, submission: &Submission<'_>) -> Result<()> {
+        // Paths are joined onto the last segment of the base URL only if it ends with a slash
+        let mut base = self.config.api_url.clone();
+        if !base.ends_with('/') {
+            base.push('/');
+        }
+        let url = base.parse::<Url>()?.join("1/submit-listens")?;
+
+        let response = http::client_for(&url)
+            .post(url)
+            .header("Authorization", format!("Token {}", self.config.token))
+            .json(submission)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            bail!("ListenBrainz submission failed with {status}: {body}");
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Service for ListenBrainz {
+    fn id(&self) -> &'static str {
+        "listenbrainz"
+    }
+
+    fn name(&self) -> &'static str {
+        "ListenBrainz"
+    }
+
+    fn batch_size(&self) -> usize {
+        BATCH_SIZE
+    }
+
+    async fn now_playing(&self, track: &Track) -> Result<()> {
+        let scrobble = Scrobble::from_track(track, 0);
+
+        self.submit_listens(&Submission {
+            listen_type: ListenType::PlayingNow,
+            payload: vec![Listen::new(&scrobble, None)],
+        })
+        .await
+    }
+
+    /// Submits a single listen as it happens, and several queued ones as an import.
+    async fn submit(&self, scrobbles: &[Scrobble]) -> Result<()> {
+        self.submit_listens(&Submission {
+            listen_type: if scrobbles.len() == 1 {
+                ListenType::Single
+            } else {
+                ListenType::Import
+            },
+            payload: scrobbles
+                .iter()
+                .map(|scrobble| Listen::new(scrobble, Some(scrobble.timestamp)))
+                .collect(),
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::Path, time::Duration};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        music::fake::track,
+        scrobble::{Scrobbler, stub::Stub, tests::queue_path},
+    };
+
+    fn listenbrainz(stub: &Stub) -> ListenBrainz {
+        ListenBrainz::new(&ListenBrainzConfig {
+            token: "token".into(),
+            api_url: stub.url.clone(),
+            ..ListenBrainzConfig::default()
+        })
+        .unwrap()
+    }
+
+    fn scrobbles(count: i64) -> Vec<Scrobble> {
+        (0..count)
+            .map(|index| Scrobble {
+                timestamp: 1_700_000_000 + index,
+                ..Scrobble::from_track(&track(&index.to_string(), &format!("Track {index}")), 0)
+            })
+            .collect()
+    }
+
+    fn queued(path: &Path) -> Vec<Scrobble> {
+        std::fs::read(path)
+            .map(|data| serde_json::from_slice(&data).unwrap())
+            .unwrap_or_default()
+    }
+
+    /// The listen type and number of listens of each submission.
+    fn submissions(stub: &Stub) -> Vec<(String, usize)> {
+        stub.requests()
+            .iter()
+            .map(|request| {
+                let json = request.json();
+                (
+                    json["listen_type"].as_str().unwrap().to_owned(),
+                    json["payload"].as_array().unwrap().len(),
+                )
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn submits_single_listens() {
+        let stub = Stub::start(&[(200, r#"{"status":"ok"}"#)]).await;
+        let listenbrainz = listenbrainz(&stub);
+
+        listenbrainz.now_playing(&track("1", "One")).await.unwrap();
+        listenbrainz.submit(&scrobbles(1)).await.unwrap();
+
+        let requests = stub.requests();
+        assert_eq!(requests[0].path, "/1/submit-listens");
+        assert_eq!(requests[0].header("authorization"), Some("Token token"));
+
+        let info = json!({
+            "duration_ms": 180_000,
+            "submission_client": "am",
+            "submission_client_version": env!("CARGO_PKG_VERSION"),
+        });
+
+        assert_eq!(
+            requests[0].json(),
+            json!({
+                "listen_type": "playing_now",
+                "payload": [{
+                    "track_metadata": {
+                        "artist_name": "Artist",
+                        "track_name": "One",
+                        "release_name": "Album",
+                        "additional_info": info,
+                    },
+                }],
+            })
+        );
+        assert_eq!(
+            requests[1].json(),
+            json!({
+                "listen_type": "single",
+                "payload": [{
+                    "listened_at": 1_700_000_000,
+                    "track_metadata": {
+                        "artist_name": "Artist",
+                        "track_name": "Track 0",
+                        "release_name": "Album",
+                        "additional_info": info,
+                    },
+                }],
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn imports_the_queue_in_batches() {
+        let stub = Stub::start(&[(200, r#"{"status":"ok"}"#)]).await;
+        let queue = queue_path();
+        std::fs::write(&queue, serde_json::to_vec(&scrobbles(250)).unwrap()).unwrap();
+
+        Scrobbler::with_queue(listenbrainz(&stub), Duration::from_secs(1), queue.clone())
+            .flush()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            submissions(&stub),
+            [
+                ("import".to_owned(), 100),
+                ("import".to_owned(), 100),
+                ("import".to_owned(), 50)
+            ]
+        );
+        assert_eq!(
+            stub.requests()[2].json()["payload"][49]["listened_at"],
+            1_700_000_249
+        );
+        assert!(queued(&queue).is_empty());
+    }
+
+    #[tokio::test]
+    async fn keeps_what_failed_to_submit() {
+        let stub = Stub::start(&[
+            (200, r#"{"status":"ok"}"#),
+            (503, r#"{"code":503,"error":"Unavailable"}"#),
+            (200, r#"{"status":"ok"}"#),
+        ])
+        .await;
+        let queue = queue_path();
+        std::fs::write(&queue, serde_json::to_vec(&scrobbles(150)).unwrap()).unwrap();
+
+        let scrobbler =
+            Scrobbler::with_queue(listenbrainz(&stub), Duration::from_secs(1), queue.clone());
+
+        let err = scrobbler.flush().await.unwrap_err();
+        assert!(err.to_string().contains("503"));
+
+        // The first batch went through, the second is kept for later
+        let kept = queued(&queue);
+        assert_eq!(kept.len(), 50);
+        assert_eq!(kept[0].track, "Track 100");
+
+        scrobbler.flush().await.unwrap();
+
+        assert_eq!(
+            submissions(&stub),
+            [
+                ("import".to_owned(), 100),
+                ("import".to_owned(), 50),
+                ("import".to_owned(), 50)
+            ]
+        );
+        assert_eq!(
+            stub.requests()[2].json()["payload"][0]["listened_at"],
+            1_700_000_100
+        );
+        assert!(queued(&queue).is_empty());
+    }
+
+    #[tokio::test]
+    async fn flushes_the_queue_one_at_a_time() {
+        let stub = Stub::start(&[(200, r#"{"status":"ok"}"#)]).await;
+        let queue = queue_path();
+        std::fs::write(&queue, serde_json::to_vec(&scrobbles(150)).unwrap()).unwrap();
+
+        // As if `am scrobble` and `am discord` were both running
+        let first =
+            Scrobbler::with_queue(listenbrainz(&stub), Duration::from_secs(1), queue.clone());
+        let second =
+            Scrobbler::with_queue(listenbrainz(&stub), Duration::from_secs(1), queue.clone());
+
+        let (first, second) = tokio::join!(first.flush(), second.flush());
+        first.unwrap();
+        second.unwrap();
+
+        assert_eq!(
+            submissions(&stub),
+            [("import".to_owned(), 100), ("import".to_owned(), 50)]
+        );
+    }
+}
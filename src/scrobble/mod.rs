@@ -0,0 +1,445 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Submitting listens to scrobbling services, following Last.fm's rules for what counts as
+//! a listen: the track is longer than 30 seconds and was played for at least half its
+//! duration or 4 minutes, whichever comes first.
+
+use std::{path::PathBuf, time::Duration};
+use tokio::fs;
+
+use async_trait::async_trait;
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::Config,
+    dirs,
+    history::{self, Listen, Observation},
+    music::Track,
+};
+
+use lastfm::LastFm;
+use listenbrainz::ListenBrainz;
+
+pub mod lastfm;
+pub mod listenbrainz;
+#[cfg(test)]
+mod stub;
+
+/// Tracks this short are never scrobbled.
+const MIN_DURATION: f64 = 30.;
+/// Tracks are scrobbled after being played for this many seconds, even if that is less
+/// than half of them.
+const MAX_THRESHOLD: f64 = 240.;
+
+/// A listen waiting to be submitted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scrobble {
+    pub artist: String,
+    pub track: String,
+    pub album: String,
+    /// Duration in seconds
+    pub duration: u32,
+    /// When the track started playing, as a Unix timestamp
+    pub timestamp: i64,
+}
+
+impl Scrobble {
+    #[expect(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn from_track(track: &Track, timestamp: i64) -> Self {
+        Self {
+            artist: track.artist.clone(),
+            track: track.name.clone(),
+            album: track.album.clone(),
+            duration: track.duration as u32,
+            timestamp,
+        }
+    }
+
+    fn new(listen: &Listen) -> Self {
+        Self::from_track(&listen.track, listen.started_at.timestamp())
+    }
+}
+
+#[async_trait]
+pub trait Service: std::fmt::Debug + Send + Sync {
+    /// Identifier of the service, used to name its queue file
+    fn id(&self) -> &'static str;
+
+    /// Name of the service to show in messages
+    fn name(&self) -> &'static str;
+
+    /// How many scrobbles can be submitted at once.
+    fn batch_size(&self) -> usize;
+
+    /// Announce that `track` started playing.
+    async fn now_playing(&self, track: &Track) -> Result<()>;
+
+    /// Submit at most [`batch_size`](Self::batch_size) scrobbles.
+    async fn submit(&self, scrobbles: &[Scrobble]) -> Result<()>;
+}
+
+#[async_trait]
+impl Service for Box<dyn Service> {
+    fn id(&self) -> &'static str {
+        (**self).id()
+    }
+
+    fn name(&self) -> &'static str {
+        (**self).name()
+    }
+
+    fn batch_size(&self) -> usize {
+        (**self).batch_size()
+    }
+
+    async fn now_playing(&self, track: &Track) -> Result<()> {
+        (**self).now_playing(track).await
+    }
+
+    async fn submit(&self, scrobbles: &[Scrobble]) -> Result<()> {
+        (**self).submit(scrobbles).await
+    }
+}
+
+/// Scrobblers for the services that are set up in `config`, or only those that are enabled
+/// for `am discord` if `discord` is set.
+pub async fn scrobblers(
+    config: &Config,
+    poll_interval: Duration,
+    discord: bool,
+) -> Result<Vec<Scrobbler<Box<dyn Service>>>> {
+    let mut services: Vec<Box<dyn Service>> = Vec::new();
+
+    if !config.lastfm.api_key.is_empty() && (!discord || config.lastfm.discord) {
+        services.push(Box::new(LastFm::authenticated(&config.lastfm).await?));
+    }
+
+    if !config.listenbrainz.token.is_empty() && (!discord || config.listenbrainz.discord) {
+        services.push(Box::new(ListenBrainz::new(&config.listenbrainz)?));
+    }
+
+    services
+        .into_iter()
+        .map(|service| Scrobbler::new(service, poll_interval))
+        .collect()
+}
+
+/// Scrobbles that couldn't be submitted yet, e.g. while offline, kept in a file until they
+/// can be.
+///
+/// `am scrobble` and `am discord` may both be flushing the same queue, so it stays locked
+/// from being loaded until it is dropped, lest one of them submit or drop the other's
+/// scrobbles.
+#[derive(Debug)]
+struct Queue {
+    path: PathBuf,
+    scrobbles: Vec<Scrobble>,
+    _lock: std::fs::File,
+}
+
+impl Queue {
+    async fn load(path: PathBuf) -> Result<Self> {
+        let lock = Self::lock(path.with_extension("lock")).await?;
+
+        let scrobbles = match fs::read(&path).await {
+            Ok(data) => serde_json::from_slice(&data)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(Self {
+            path,
+            scrobbles,
+            _lock: lock,
+        })
+    }
+
+    /// Wait for an exclusive lock on the file at `path`, which is released when it is closed.
+    async fn lock(path: PathBuf) -> Result<std::fs::File> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        tokio::task::spawn_blocking(move || {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(path)?;
+            file.lock()?;
+
+            Ok(file)
+        })
+        .await?
+    }
+
+    async fn save(&self) -> Result<()> {
+        if self.scrobbles.is_empty() {
+            return match fs::remove_file(&self.path).await {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+                _ => Ok(()),
+            };
+        }
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        fs::write(&self.path, serde_json::to_vec_pretty(&self.scrobbles)?).await?;
+
+        Ok(())
+    }
+}
+
+/// Follows what is playing from poll to poll and scrobbles it to a [`Service`].
+#[derive(Debug)]
+pub struct Scrobbler<S> {
+    service: S,
+    /// Where scrobbles are queued until they have been submitted
+    queue: PathBuf,
+    current: Option<Listen>,
+    /// Whether the current listen has been scrobbled already
+    scrobbled: bool,
+    end_margin: f64,
+}
+
+impl<S: Service> Scrobbler<S> {
+    /// Queue scrobbles in `$XDG_STATE_HOME/am/<service>-queue.json`.
+    pub fn new(service: S, poll_interval: Duration) -> Result<Self> {
+        let queue = dirs::state_dir()?.join(format!("{}-queue.json", service.id()));
+        Ok(Self::with_queue(service, poll_interval, queue))
+    }
+
+    pub fn with_queue(service: S, poll_interval: Duration, queue: PathBuf) -> Self {
+        Self {
+            service,
+            queue,
+            current: None,
+            scrobbled: false,
+            end_margin: history::end_margin(poll_interval),
+        }
+    }
+
+    pub fn service(&self) -> &S {
+        &self.service
+    }
+
+    /// Note that `track` is playing at `position`, announcing it if it just started and
+    /// scrobbling it once it has been played for long enough.
+    pub async fn observe(&mut self, track: &Track, position: f64) -> Result<()> {
+        if let Some(current) = &mut self.current
+            && current.update(track, position, self.end_margin)
+        {
+            let threshold = (current.track.duration / 2.).min(MAX_THRESHOLD);
+
+            if !self.scrobbled
+                && current.track.duration > MIN_DURATION
+                && current.played >= threshold
+            {
+                let scrobble = Scrobble::new(current);
+                self.scrobbled = true;
+                return self.enqueue(scrobble).await;
+            }
+
+            return Ok(());
+        }
+
+        self.current = Some(Listen::new(track, position));
+        self.scrobbled = false;
+
+        // Take the chance to submit what was queued while offline
+        let result = self.service.now_playing(track).await;
+        self.flush().await?;
+
+        result
+    }
+
+    /// Forget the current track, e.g. because Music was quit.
+    pub fn stop(&mut self) {
+        self.current = None;
+    }
+
+    /// Scrobble what a poll found to be playing.
+    pub async fn update(&mut self, observation: &Observation) -> Result<()> {
+        match observation {
+            Observation::Playing(track, position) => self.observe(track, *position).await,
+            Observation::Paused => Ok(()),
+            Observation::Stopped => {
+                self.stop();
+                Ok(())
+            }
+        }
+    }
+
+    async fn enqueue(&self, scrobble: Scrobble) -> Result<()> {
+        let mut queue = Queue::load(self.queue.clone()).await?;
+        queue.scrobbles.push(scrobble);
+        queue.save().await?;
+        drop(queue);
+
+        self.flush().await
+    }
+
+    /// Submit queued scrobbles, keeping the ones that couldn't be submitted.
+    pub async fn flush(&self) -> Result<()> {
+        let mut queue = Queue::load(self.queue.clone()).await?;
+
+        while !queue.scrobbles.is_empty() {
+            let batch = queue.scrobbles.len().min(self.service.batch_size());
+
+            if let Err(err) = self.service.submit(&queue.scrobbles[..batch]).await {
+                queue.save().await?;
+                return Err(err);
+            }
+
+            queue.scrobbles.drain(..batch);
+        }
+
+        queue.save().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    };
+
+    use super::*;
+    use crate::music::{
+        PlayerBackend as _,
+        fake::{ScriptedBackend, Snapshot, track},
+    };
+
+    /// Records what would be sent to a scrobbling service.
+    #[derive(Debug, Clone, Default)]
+    struct Recording {
+        now_playing: Arc<Mutex<Vec<String>>>,
+        /// The names of the tracks in each submitted batch
+        submitted: Arc<Mutex<Vec<Vec<String>>>>,
+        /// Whether submitting fails, e.g. because the service can't be reached
+        offline: Arc<AtomicBool>,
+    }
+
+    #[async_trait]
+    impl Service for Recording {
+        fn id(&self) -> &'static str {
+            "recording"
+        }
+
+        fn name(&self) -> &'static str {
+            "Recording"
+        }
+
+        fn batch_size(&self) -> usize {
+            2
+        }
+
+        async fn now_playing(&self, track: &Track) -> Result<()> {
+            self.now_playing.lock().unwrap().push(track.name.clone());
+            Ok(())
+        }
+
+        async fn submit(&self, scrobbles: &[Scrobble]) -> Result<()> {
+            if self.offline.load(Ordering::Relaxed) {
+                eyre::bail!("Offline");
+            }
+
+            self.submitted
+                .lock()
+                .unwrap()
+                .push(scrobbles.iter().map(|s| s.track.clone()).collect());
+            Ok(())
+        }
+    }
+
+    /// A queue file of its own for each test.
+    pub(super) fn queue_path() -> PathBuf {
+        std::env::temp_dir().join(format!("am-test-{}-queue.json", uuid::Uuid::new_v4()))
+    }
+
+    async fn poll<S: Service>(backend: &ScriptedBackend, scrobbler: &mut Scrobbler<S>) {
+        let observation = Observation::poll(backend).await.unwrap();
+        scrobbler.update(&observation).await.unwrap();
+    }
+
+    /// Keep playing for `seconds`, a second per poll so that it counts as listening.
+    async fn listen<S: Service>(
+        backend: &ScriptedBackend,
+        scrobbler: &mut Scrobbler<S>,
+        seconds: usize,
+    ) {
+        for _ in 0..seconds {
+            let position = backend.player_position().await.unwrap().unwrap_or_default();
+            backend.set_player_position(position + 1.).await.unwrap();
+            poll(backend, scrobbler).await;
+        }
+    }
+
+    fn scrobbler(service: &Recording) -> Scrobbler<Recording> {
+        Scrobbler::with_queue(service.clone(), Duration::from_secs(1), queue_path())
+    }
+
+    #[tokio::test]
+    async fn scrobbles_after_half_the_track() {
+        let service = Recording::default();
+        let mut scrobbler = scrobbler(&service);
+        let backend = ScriptedBackend::new(Snapshot::playing(track("1", "One")));
+
+        poll(&backend, &mut scrobbler).await;
+        listen(&backend, &mut scrobbler, 89).await;
+        assert_eq!(*service.now_playing.lock().unwrap(), ["One"]);
+        assert!(service.submitted.lock().unwrap().is_empty());
+
+        listen(&backend, &mut scrobbler, 1).await;
+        assert_eq!(*service.submitted.lock().unwrap(), [["One"]]);
+
+        // Only once per listen
+        listen(&backend, &mut scrobbler, 60).await;
+        assert_eq!(service.submitted.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn scrobbles_long_tracks_after_four_minutes() {
+        let service = Recording::default();
+        let mut scrobbler = scrobbler(&service);
+        let backend = ScriptedBackend::new(Snapshot::playing(Track {
+            duration: 600.,
+            ..track("1", "One")
+        }));
+
+        poll(&backend, &mut scrobbler).await;
+        listen(&backend, &mut scrobbler, 239).await;
+        assert!(service.submitted.lock().unwrap().is_empty());
+
+        listen(&backend, &mut scrobbler, 1).await;
+        assert_eq!(service.submitted.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn skips_short_tracks_and_seeking() {
+        let service = Recording::default();
+        let mut scrobbler = scrobbler(&service);
+        let backend = ScriptedBackend::new(Snapshot::playing(Track {
+            duration: 30.,
+            ..track("1", "One")
+        }))
+        .then(Snapshot::playing(track("2", "Two")));
+
+        poll(&backend, &mut scrobbler).await;
+        listen(&backend, &mut scrobbler, 30).await;
+
+        backend.advance();
+        poll(&backend, &mut scrobbler).await;
+        listen(&backend, &mut scrobbler, 10).await;
+        backend.set_player_position(170.).await.unwrap();
+        listen(&backend, &mut scrobbler, 5).await;
+
+        assert_eq!(*service.now_playing.lock().unwrap(), ["One", "Two"]);
+        assert!(service.submitted.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,135 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! A loopback HTTP server standing in for a scrobbling API, so that services can be
+//! exercised without the network.
+
+use std::sync::{Arc, Mutex};
+
+use reqwest::Url;
+use tokio::{
+    io::{AsyncBufReadExt as _, AsyncReadExt as _, AsyncWriteExt as _, BufReader},
+    net::{TcpListener, TcpStream},
+};
+
+/// A request received by a [`Stub`].
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub path: String,
+    /// Headers with lowercase names
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The fields of a form-encoded body.
+    pub fn form(&self) -> Vec<(String, String)> {
+        let mut url = Url::parse("http://localhost/").unwrap();
+        url.set_query(Some(&self.body));
+        url.query_pairs().into_owned().collect()
+    }
+
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_str(&self.body).unwrap()
+    }
+}
+
+/// Answers each request with the next of a list of responses, repeating the last one once
+/// they run out, and records the requests.
+#[derive(Debug, Clone)]
+pub struct Stub {
+    /// Base URL of the server, ending with a slash
+    pub url: String,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl Stub {
+    /// Start a server answering with a status code and JSON body for each request.
+    pub async fn start(responses: &[(u16, &'static str)]) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let requests = Arc::<Mutex<Vec<Request>>>::default();
+        let responses = responses.to_vec();
+
+        tokio::spawn({
+            let requests = requests.clone();
+
+            async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    tokio::spawn(serve(stream, requests.clone(), responses.clone()));
+                }
+            }
+        });
+
+        Self { url, requests }
+    }
+
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// Answer the requests on a connection until the client closes it.
+async fn serve(
+    stream: TcpStream,
+    requests: Arc<Mutex<Vec<Request>>>,
+    responses: Vec<(u16, &'static str)>,
+) {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await.unwrap_or_default() == 0 {
+            return;
+        }
+
+        let path = line.split(' ').nth(1).unwrap_or_default().to_owned();
+        let mut headers = Vec::new();
+
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).await.unwrap();
+
+            let Some((name, value)) = line.trim_end().split_once(':') else {
+                break;
+            };
+            headers.push((name.to_lowercase(), value.trim().to_owned()));
+        }
+
+        let length = headers
+            .iter()
+            .find(|(name, _)| name == "content-length")
+            .map_or(0, |(_, value)| value.parse().unwrap());
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).await.unwrap();
+
+        let index = {
+            let mut requests = requests.lock().unwrap();
+            requests.push(Request {
+                path,
+                headers,
+                body: String::from_utf8(body).unwrap(),
+            });
+            requests.len() - 1
+        };
+
+        let (status, body) = responses[index.min(responses.len() - 1)];
+        let response = format!(
+            "HTTP/1.1 {status} Stub\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{body}",
+            body.len()
+        );
+
+        if writer.write_all(response.as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
@@ -0,0 +1,64 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{fs::Permissions, os::unix::fs::PermissionsExt as _, path::PathBuf};
+use tokio::{fs, io::AsyncWriteExt as _};
+
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{dirs, scrobble::lastfm};
+
+/// State that `am` keeps between invocations, stored in `$XDG_STATE_HOME/am/state.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct State {
+    /// The volume before `am volume mute`, if currently muted
+    pub muted_volume: Option<u8>,
+    /// The Last.fm session authorized with `am scrobble auth`
+    pub lastfm_session: Option<lastfm::Session>,
+}
+
+impl State {
+    fn path() -> Result<PathBuf> {
+        Ok(dirs::state_dir()?.join("state.json"))
+    }
+
+    pub async fn load() -> Result<Self> {
+        match fs::read(Self::path()?).await {
+            Ok(data) => Ok(serde_json::from_slice(&data)?),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub async fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        // The Last.fm session key is a secret, so only the user may read the file. It is
+        // written next to it and renamed over it so that it is never readable by others, or
+        // left half-written.
+        let temp = path.with_extension("json.tmp");
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(&temp)
+            .await?;
+
+        // The mode only applies if the file was created just now
+        file.set_permissions(Permissions::from_mode(0o600)).await?;
+        file.write_all(&serde_json::to_vec_pretty(self)?).await?;
+        file.sync_all().await?;
+
+        fs::rename(temp, path).await?;
+
+        Ok(())
+    }
+}
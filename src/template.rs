@@ -0,0 +1,445 @@
+// SPDX-FileCopyrightText: 2025 Ryan Cao <hello@ryanccn.dev>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! A small template language for rendering playback state as a single line.
+//!
+//! Placeholders are written as `{field}`, optionally followed by filters separated
+//! by `|`, e.g. `{name|truncate:20|upper}`. Filter arguments follow a `:`. Literal
+//! braces are written as `{{` and `}}`.
+
+use owo_colors::OwoColorize as _;
+use thiserror::Error;
+
+use crate::{
+    format,
+    music::{PlayerState, Playlist, Track},
+};
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum TemplateError {
+    #[error("unclosed `{{` at column {0}")]
+    Unclosed(usize),
+    #[error("unmatched `}}` at column {0} (use `}}}}` for a literal brace)")]
+    Unmatched(usize),
+    #[error("empty placeholder at column {0}")]
+    EmptyPlaceholder(usize),
+    #[error("unknown field `{field}` at column {column}")]
+    UnknownField { field: String, column: usize },
+    #[error("unknown filter `{filter}` at column {column}")]
+    UnknownFilter { filter: String, column: usize },
+    #[error("filter `{filter}` at column {column} {message}")]
+    InvalidArgument {
+        filter: String,
+        column: usize,
+        message: &'static str,
+    },
+}
+
+/// The values a template can refer to.
+#[derive(Debug, Clone, Copy)]
+pub struct Context<'a> {
+    pub state: PlayerState,
+    pub position: Option<f64>,
+    pub track: Option<&'a Track>,
+    pub playlist: Option<&'a Playlist>,
+    pub volume: Option<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Id,
+    Name,
+    Album,
+    Artist,
+    Duration,
+    Position,
+    Remaining,
+    Progress,
+    State,
+    StateIcon,
+    Playlist,
+    PlaylistDuration,
+    Volume,
+}
+
+impl Field {
+    fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "id" => Self::Id,
+            "name" => Self::Name,
+            "album" => Self::Album,
+            "artist" => Self::Artist,
+            "duration" => Self::Duration,
+            "position" => Self::Position,
+            "remaining" => Self::Remaining,
+            "progress" => Self::Progress,
+            "state" => Self::State,
+            "state_icon" => Self::StateIcon,
+            "playlist" => Self::Playlist,
+            "playlist_duration" => Self::PlaylistDuration,
+            "volume" => Self::Volume,
+            _ => return None,
+        })
+    }
+
+    #[expect(clippy::cast_possible_truncation)]
+    fn value(self, ctx: &Context) -> Option<String> {
+        match self {
+            Self::Id => ctx.track.map(|t| t.id.clone()),
+            Self::Name => ctx.track.map(|t| t.name.clone()),
+            Self::Album => ctx.track.map(|t| t.album.clone()),
+            Self::Artist => ctx.track.map(|t| t.artist.clone()),
+            Self::Duration => ctx
+                .track
+                .map(|t| format::format_duration_plain(t.duration as i32)),
+            Self::Position => ctx
+                .position
+                .map(|p| format::format_duration_plain(p as i32)),
+            Self::Remaining => ctx
+                .track
+                .zip(ctx.position)
+                .map(|(t, p)| format::format_duration_plain((t.duration - p).max(0.) as i32)),
+            Self::Progress => ctx
+                .track
+                .zip(ctx.position)
+                .filter(|(t, _)| t.duration > 0.)
+                .map(|(t, p)| format!("{:.0}", (p / t.duration * 100.).clamp(0., 100.))),
+            Self::State => Some(ctx.state.to_string()),
+            Self::StateIcon => Some(ctx.state.to_icon()),
+            Self::Playlist => ctx.playlist.map(|p| p.name.clone()),
+            Self::PlaylistDuration => ctx
+                .playlist
+                .map(|p| format::format_duration_plain(p.duration)),
+            Self::Volume => ctx.volume.map(|v| v.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    Upper,
+    Lower,
+    Truncate(usize),
+    Default(String),
+    Bold,
+    Dimmed,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+}
+
+impl Filter {
+    fn parse(s: &str, column: usize) -> Result<Self, TemplateError> {
+        let (name, arg) = match s.split_once(':') {
+            Some((name, arg)) => (name.trim(), Some(arg)),
+            None => (s.trim(), None),
+        };
+
+        let invalid = |message| TemplateError::InvalidArgument {
+            filter: name.to_owned(),
+            column,
+            message,
+        };
+
+        let filter = match name {
+            "upper" => Self::Upper,
+            "lower" => Self::Lower,
+            "truncate" => Self::Truncate(
+                arg.ok_or_else(|| invalid("requires a length, e.g. `truncate:20`"))?
+                    .trim()
+                    .parse()
+                    .map_err(|_| invalid("requires a non-negative integer length"))?,
+            ),
+            "default" => Self::Default(
+                arg.ok_or_else(|| invalid("requires a value, e.g. `default:none`"))?
+                    .to_owned(),
+            ),
+            "bold" => Self::Bold,
+            "dimmed" => Self::Dimmed,
+            "red" => Self::Red,
+            "green" => Self::Green,
+            "yellow" => Self::Yellow,
+            "blue" => Self::Blue,
+            "magenta" => Self::Magenta,
+            "cyan" => Self::Cyan,
+            _ => {
+                return Err(TemplateError::UnknownFilter {
+                    filter: name.to_owned(),
+                    column,
+                });
+            }
+        };
+
+        if arg.is_some() && !matches!(filter, Self::Truncate(_) | Self::Default(_)) {
+            return Err(invalid("does not take an argument"));
+        }
+
+        Ok(filter)
+    }
+
+    fn apply(&self, value: Option<String>) -> Option<String> {
+        if let Self::Default(default) = self {
+            return Some(value.filter(|v| !v.is_empty()).unwrap_or(default.clone()));
+        }
+
+        let value = value?;
+
+        Some(match self {
+            Self::Upper => value.to_uppercase(),
+            Self::Lower => value.to_lowercase(),
+            Self::Truncate(len) => truncate(&value, *len),
+            Self::Bold => value.bold().to_string(),
+            Self::Dimmed => value.dimmed().to_string(),
+            Self::Red => value.red().to_string(),
+            Self::Green => value.green().to_string(),
+            Self::Yellow => value.yellow().to_string(),
+            Self::Blue => value.blue().to_string(),
+            Self::Magenta => value.magenta().to_string(),
+            Self::Cyan => value.cyan().to_string(),
+            Self::Default(_) => unreachable!(),
+        })
+    }
+}
+
+/// Truncate `value` to at most `len` characters, ending with an ellipsis if anything was cut off.
+pub fn truncate(value: &str, len: usize) -> String {
+    if value.chars().count() <= len {
+        value.to_owned()
+    } else if len == 0 {
+        String::new()
+    } else {
+        value.chars().take(len - 1).chain(['…']).collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    Placeholder { field: Field, filters: Vec<Filter> },
+}
+
+/// A parsed template.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Self, TemplateError> {
+        let chars = source.chars().collect::<Vec<_>>();
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut i = 0;
+
+        while i < chars.len() {
+            match chars[i] {
+                '{' if chars.get(i + 1) == Some(&'{') => {
+                    literal.push('{');
+                    i += 2;
+                }
+                '}' if chars.get(i + 1) == Some(&'}') => {
+                    literal.push('}');
+                    i += 2;
+                }
+                '}' => return Err(TemplateError::Unmatched(i + 1)),
+                '{' => {
+                    let column = i + 1;
+                    let end = chars[i..]
+                        .iter()
+                        .position(|&c| c == '}')
+                        .map(|offset| i + offset)
+                        .ok_or(TemplateError::Unclosed(column))?;
+
+                    let inner = chars[i + 1..end].iter().collect::<String>();
+                    let mut parts = inner.split('|');
+
+                    let field = parts.next().unwrap_or_default().trim();
+                    if field.is_empty() {
+                        return Err(TemplateError::EmptyPlaceholder(column));
+                    }
+
+                    let field = Field::parse(field).ok_or_else(|| TemplateError::UnknownField {
+                        field: field.to_owned(),
+                        column,
+                    })?;
+                    let filters = parts
+                        .map(|f| Filter::parse(f, column))
+                        .collect::<Result<Vec<_>, _>>()?;
+
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Placeholder { field, filters });
+
+                    i = end + 1;
+                }
+                c => {
+                    literal.push(c);
+                    i += 1;
+                }
+            }
+        }
+
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        Ok(Self { segments })
+    }
+
+    /// Render the template. Fields without a value (e.g. the track when playback is stopped)
+    /// render as empty strings unless given a `default`.
+    pub fn render(&self, ctx: &Context) -> String {
+        let mut output = String::new();
+
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(literal) => output.push_str(literal),
+                Segment::Placeholder { field, filters } => {
+                    let value = filters
+                        .iter()
+                        .fold(field.value(ctx), |value, filter| filter.apply(value));
+
+                    output.push_str(&value.unwrap_or_default());
+                }
+            }
+        }
+
+        output
+    }
+}
+
+impl std::str::FromStr for Template {
+    type Err = TemplateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music::fake::track;
+
+    fn render(source: &str, ctx: &Context) -> String {
+        Template::parse(source).unwrap().render(ctx)
+    }
+
+    fn playing(track: &Track) -> Context<'_> {
+        Context {
+            state: PlayerState::Playing,
+            position: Some(65.),
+            track: Some(track),
+            playlist: None,
+            volume: Some(40),
+        }
+    }
+
+    #[test]
+    fn renders_fields() {
+        let track = track("1", "Bohemian Rhapsody");
+        let ctx = playing(&track);
+
+        assert_eq!(
+            render("{name} by {artist} at {volume}%", &ctx),
+            "Bohemian Rhapsody by Artist at 40%"
+        );
+        assert_eq!(render("{{{name}}}", &ctx), "{Bohemian Rhapsody}");
+    }
+
+    #[test]
+    fn renders_durations() {
+        let track = track("1", "One");
+        let ctx = playing(&track);
+
+        assert_eq!(
+            render("{position}/{duration}, {remaining} left", &ctx),
+            "1m5s/3m0s, 1m55s left"
+        );
+        assert_eq!(render("{progress}%", &ctx), "36%");
+    }
+
+    #[test]
+    fn applies_filters_in_order() {
+        let track = track("1", "Bohemian Rhapsody");
+        let ctx = playing(&track);
+
+        assert_eq!(render("{name|upper}", &ctx), "BOHEMIAN RHAPSODY");
+        assert_eq!(render("{name|truncate:9}", &ctx), "Bohemian…");
+        assert_eq!(render("{name|truncate:9|lower}", &ctx), "bohemian…");
+        assert_eq!(render("{name|truncate:50}", &ctx), "Bohemian Rhapsody");
+        assert_eq!(
+            render("{name|bold}", &ctx),
+            "Bohemian Rhapsody".bold().to_string()
+        );
+    }
+
+    #[test]
+    fn missing_fields_render_empty() {
+        let ctx = Context {
+            state: PlayerState::Stopped,
+            position: None,
+            track: None,
+            playlist: None,
+            volume: None,
+        };
+
+        assert_eq!(render("[{name}] {state}", &ctx), "[] stopped");
+        assert_eq!(render("{playlist|upper|default:none}", &ctx), "none");
+    }
+
+    #[test]
+    fn reports_columns() {
+        let cases = [
+            ("{name", TemplateError::Unclosed(1)),
+            ("abc } def", TemplateError::Unmatched(5)),
+            ("x {}", TemplateError::EmptyPlaceholder(3)),
+            (
+                "{name} {nope}",
+                TemplateError::UnknownField {
+                    field: "nope".into(),
+                    column: 8,
+                },
+            ),
+            (
+                "ab {name|shout}",
+                TemplateError::UnknownFilter {
+                    filter: "shout".into(),
+                    column: 4,
+                },
+            ),
+            (
+                "{name|truncate:x}",
+                TemplateError::InvalidArgument {
+                    filter: "truncate".into(),
+                    column: 1,
+                    message: "requires a non-negative integer length",
+                },
+            ),
+            (
+                "{name|upper:1}",
+                TemplateError::InvalidArgument {
+                    filter: "upper".into(),
+                    column: 1,
+                    message: "does not take an argument",
+                },
+            ),
+        ];
+
+        for (source, error) in cases {
+            assert_eq!(Template::parse(source), Err(error), "{source}");
+        }
+    }
+
+    #[test]
+    fn truncates_by_characters() {
+        assert_eq!(truncate("Sigur Rós", 9), "Sigur Rós");
+        assert_eq!(truncate("Sigur Rós", 8), "Sigur R…");
+        assert_eq!(truncate("abc", 0), "");
+    }
+}